@@ -5,6 +5,9 @@ pub mod consts {
 
 use consts::Gl as InnerGl;
 
+// The header which is prepended to all shader sources before compiling
+pub(crate) const SHADER_HEADER: &str = "#version 330 core\n";
+
 pub type AttributeLocation = u32;
 pub type UniformLocation = u32;
 pub type Shader = u32;
@@ -57,10 +60,9 @@ impl Glstruct {
         Some(id)
     }
 
-    pub fn compile_shader(&self, source: &str, shader: &Shader)
+    pub fn compile_shader(&self, source: &str, shader: &Shader) -> bool
     {
-        let header = "#version 330 core\n";
-        let s: &str = &[header, source].concat();
+        let s: &str = &[SHADER_HEADER, source].concat();
 
         use std::ffi::{CStr, CString};
         let c_str: &CStr = &CString::new(s).unwrap();
@@ -69,6 +71,12 @@ impl Glstruct {
             self.inner.ShaderSource(*shader, 1, &c_str.as_ptr(), std::ptr::null());
             self.inner.CompileShader(*shader);
         }
+
+        let mut success: consts::types::GLint = 1;
+        unsafe {
+            self.inner.GetShaderiv(*shader, consts::COMPILE_STATUS, &mut success);
+        }
+        success == 1
     }
 
     pub fn get_shader_info_log(&self, shader: &Shader) -> Option<String> {
@@ -15,6 +15,9 @@ pub use web_sys::WebGlVertexArrayObject as VertexArrayObject;
 pub use web_sys::WebGlActiveInfo as ActiveInfo;
 pub use web_sys::WebGlSync as Sync;
 
+// The header which is prepended to all shader sources before compiling
+pub(crate) const SHADER_HEADER: &str = "#version 300 es\nprecision highp float;\nprecision highp int;\nprecision highp sampler2DArray;\n";
+
 #[derive(Clone)]
 pub struct Glstruct {
     inner: InnerGl,
@@ -97,13 +100,13 @@ impl Glstruct {
         );
     }
 
-    pub fn compile_shader(&self, source: &str, shader: &Shader)
+    pub fn compile_shader(&self, source: &str, shader: &Shader) -> bool
     {
-        let header = "#version 300 es\nprecision highp float;\nprecision highp int;\nprecision highp sampler2DArray;\n";
-        let s: &str = &[header, source].concat();
+        let s: &str = &[SHADER_HEADER, source].concat();
 
         self.inner.shader_source(shader, s);
         self.inner.compile_shader(shader);
+        self.inner.get_shader_parameter(shader, consts::COMPILE_STATUS).as_bool().unwrap_or(false)
     }
 
     pub fn create_program(&self) -> Program
@@ -50,6 +50,7 @@ pub use program::*;
 pub enum Error {
     UnknownShaderType {message: String},
     FailedToCreateShader {shader_type: String, message: String},
+    FailedToCompileShader {shader_type: String, errors: Vec<ShaderCompileError>},
     FailedToLinkProgram {message: String},
     FailedToFindAttribute {message: String},
     FailedToFindUniform {message: String},
@@ -58,5 +59,35 @@ pub enum Error {
     FailedToWriteToRenderTarget {message: String},
     FailedToCreateTexture {message: String},
     FailedToUpdateBuffer {message: String},
-    FailedToCreateMesh {message: String}
-}
\ No newline at end of file
+    FailedToCreateMesh {message: String, report: Option<crate::definition::MeshValidationReport>},
+    FailedToComputeTangents {message: String}
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::UnknownShaderType {message} => write!(f, "unknown shader type: {}", message),
+            Error::FailedToCreateShader {shader_type, message} => write!(f, "failed to create {}: {}", shader_type.to_lowercase(), message),
+            Error::FailedToCompileShader {shader_type, errors} => {
+                write!(f, "failed to compile {}:", shader_type.to_lowercase())?;
+                for error in errors {
+                    write!(f, "\n  {}", error)?;
+                }
+                Ok(())
+            },
+            Error::FailedToLinkProgram {message} => write!(f, "failed to link shader program: {}", message),
+            Error::FailedToFindAttribute {message} => write!(f, "failed to find attribute: {}", message),
+            Error::FailedToFindUniform {message} => write!(f, "failed to find uniform: {}", message),
+            Error::FailedToCreateFramebuffer {message} => write!(f, "failed to create framebuffer: {}", message),
+            Error::FailedToCopyFromRenderTarget {message} => write!(f, "failed to copy from render target: {}", message),
+            Error::FailedToWriteToRenderTarget {message} => write!(f, "failed to write to render target: {}", message),
+            Error::FailedToCreateTexture {message} => write!(f, "failed to create texture: {}", message),
+            Error::FailedToUpdateBuffer {message} => write!(f, "failed to update buffer: {}", message),
+            Error::FailedToCreateMesh {message, report: None} => write!(f, "failed to create mesh: {}", message),
+            Error::FailedToCreateMesh {message, report: Some(report)} => write!(f, "failed to create mesh: {}\n{}", message, report),
+            Error::FailedToComputeTangents {message} => write!(f, "failed to compute tangents: {}", message)
+        }
+    }
+}
+
+impl std::error::Error for Error {}
\ No newline at end of file
@@ -2,6 +2,7 @@ use std::collections::HashMap;
 use std::cell::RefCell;
 use crate::math::*;
 use crate::core::*;
+use crate::definition::PrimitiveType;
 use crate::context::{Context, consts};
 
 ///
@@ -27,8 +28,8 @@ impl Program
             .ok_or(Error::FailedToCreateShader{ shader_type: "Vertex shader".to_string(), message:"Unable to create shader object".to_string() })?;
         let frag_shader = context.create_shader(consts::FRAGMENT_SHADER)
             .ok_or(Error::FailedToCreateShader{ shader_type: "Fragment shader".to_string(), message:"Unable to create shader object".to_string() })?;
-        context.compile_shader(vertex_shader_source, &vert_shader);
-        context.compile_shader(fragment_shader_source, &frag_shader);
+        let vert_compiled = context.compile_shader(vertex_shader_source, &vert_shader);
+        let frag_compiled = context.compile_shader(fragment_shader_source, &frag_shader);
 
         let id = context.create_program();
         context.attach_shader(&id, &vert_shader);
@@ -36,6 +37,15 @@ impl Program
         let success = context.link_program(&id);
 
         if !success {
+            let shaders = [("Vertex shader", vert_compiled, &vert_shader, vertex_shader_source), ("Fragment shader", frag_compiled, &frag_shader, fragment_shader_source)];
+            for (shader_type, _, shader, source) in shaders.iter().filter(|(_, compiled, _, _)| !compiled) {
+                if let Some(log) = context.get_shader_info_log(shader) {
+                    let errors = shader_compile_errors(&log, source);
+                    if !errors.is_empty() {
+                        return Err(Error::FailedToCompileShader { shader_type: shader_type.to_string(), errors });
+                    }
+                }
+            }
             let mut message = "Failed to compile shader program:\n".to_string();
             if let Some(log) = context.get_program_info_log(&id) {
                 message = format!("{}\nLink error: {}", message, log);
@@ -259,11 +269,19 @@ impl Program
     }
 
     pub fn draw_arrays(&self, render_states: RenderStates, viewport: Viewport, count: u32)
+    {
+        self.draw_primitive_arrays(PrimitiveType::Triangles, render_states, viewport, count);
+    }
+
+    ///
+    /// Draws the first **count** vertices as the given type of primitives, ie. as triangles, lines or points.
+    ///
+    pub fn draw_primitive_arrays(&self, primitive_type: PrimitiveType, render_states: RenderStates, viewport: Viewport, count: u32)
     {
         Self::set_viewport(&self.context, viewport);
         Self::set_states(&self.context, render_states);
         self.set_used();
-        self.context.draw_arrays(consts::TRIANGLES, 0, count);
+        self.context.draw_arrays(primitive_mode(primitive_type), 0, count);
         for location in self.vertex_attributes.values() {
             self.context.disable_vertex_attrib_array(*location);
         }
@@ -271,11 +289,19 @@ impl Program
     }
 
     pub fn draw_arrays_instanced(&self, render_states: RenderStates, viewport: Viewport, count: u32, instance_count: u32)
+    {
+        self.draw_primitive_arrays_instanced(PrimitiveType::Triangles, render_states, viewport, count, instance_count);
+    }
+
+    ///
+    /// Draws **instance_count** instances of the first **count** vertices as the given type of primitives, ie. as triangles, lines or points.
+    ///
+    pub fn draw_primitive_arrays_instanced(&self, primitive_type: PrimitiveType, render_states: RenderStates, viewport: Viewport, count: u32, instance_count: u32)
     {
         Self::set_viewport(&self.context, viewport);
         Self::set_states(&self.context, render_states);
         self.set_used();
-        self.context.draw_arrays_instanced(consts::TRIANGLES, 0, count, instance_count);
+        self.context.draw_arrays_instanced(primitive_mode(primitive_type), 0, count, instance_count);
         self.context.unbind_buffer(consts::ELEMENT_ARRAY_BUFFER);
         for location in self.vertex_attributes.values() {
             self.context.disable_vertex_attrib_array(*location);
@@ -288,13 +314,26 @@ impl Program
         self.draw_subset_of_elements(render_states, viewport, element_buffer, 0,element_buffer.count() as u32);
     }
 
+    ///
+    /// Draws the vertices given by the indices in the element buffer as the given type of primitives, ie. as triangles, lines or points.
+    ///
+    pub fn draw_primitive_elements(&self, primitive_type: PrimitiveType, render_states: RenderStates, viewport: Viewport, element_buffer: &ElementBuffer)
+    {
+        self.draw_elements_with_mode(primitive_mode(primitive_type), render_states, viewport, element_buffer, 0, element_buffer.count() as u32);
+    }
+
     pub fn draw_subset_of_elements(&self, render_states: RenderStates, viewport: Viewport, element_buffer: &ElementBuffer, first: u32, count: u32)
+    {
+        self.draw_elements_with_mode(consts::TRIANGLES, render_states, viewport, element_buffer, first, count);
+    }
+
+    fn draw_elements_with_mode(&self, mode: u32, render_states: RenderStates, viewport: Viewport, element_buffer: &ElementBuffer, first: u32, count: u32)
     {
         Self::set_viewport(&self.context, viewport);
         Self::set_states(&self.context, render_states);
         self.set_used();
         element_buffer.bind();
-        self.context.draw_elements(consts::TRIANGLES, count, consts::UNSIGNED_INT, first);
+        self.context.draw_elements(mode, count, consts::UNSIGNED_INT, first);
         self.context.unbind_buffer(consts::ELEMENT_ARRAY_BUFFER);
 
         for location in self.vertex_attributes.values() {
@@ -304,12 +343,20 @@ impl Program
     }
 
     pub fn draw_elements_instanced(&self, render_states: RenderStates, viewport: Viewport, element_buffer: &ElementBuffer, count: u32)
+    {
+        self.draw_primitive_elements_instanced(PrimitiveType::Triangles, render_states, viewport, element_buffer, count);
+    }
+
+    ///
+    /// Draws **count** instances of the vertices given by the indices in the element buffer as the given type of primitives, ie. as triangles, lines or points.
+    ///
+    pub fn draw_primitive_elements_instanced(&self, primitive_type: PrimitiveType, render_states: RenderStates, viewport: Viewport, element_buffer: &ElementBuffer, count: u32)
     {
         Self::set_viewport(&self.context, viewport);
         Self::set_states(&self.context, render_states);
         self.set_used();
         element_buffer.bind();
-        self.context.draw_elements_instanced(consts::TRIANGLES, element_buffer.count() as u32, consts::UNSIGNED_INT, 0, count);
+        self.context.draw_elements_instanced(primitive_mode(primitive_type), element_buffer.count() as u32, consts::UNSIGNED_INT, 0, count);
         self.context.unbind_buffer(consts::ELEMENT_ARRAY_BUFFER);
         for location in self.vertex_attributes.values() {
             self.context.disable_vertex_attrib_array(*location);
@@ -495,8 +542,190 @@ impl Program
     }
 }
 
+fn primitive_mode(primitive_type: PrimitiveType) -> u32 {
+    match primitive_type {
+        PrimitiveType::Triangles => consts::TRIANGLES,
+        PrimitiveType::Lines => consts::LINES,
+        PrimitiveType::Points => consts::POINTS
+    }
+}
+
+///
+/// A compile error in a shader, see [Error::FailedToCompileShader](crate::Error::FailedToCompileShader).
+///
+#[derive(Debug, Clone)]
+pub struct ShaderCompileError {
+    /// The name of the shared snippet which contains the error or `None` if the error is in the rest of the shader source.
+    pub snippet: Option<String>,
+    /// The line number in the snippet or in the rest of the shader source, where each included snippet counts as one line, starting at 1.
+    pub line: usize,
+    /// The offending source line.
+    pub source_line: String,
+    /// The message reported by the driver.
+    pub message: String
+}
+
+impl std::fmt::Display for ShaderCompileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: {}\n    {}", self.snippet.as_deref().unwrap_or("shader"), self.line, self.message, self.source_line.trim())
+    }
+}
+
+// Snippets which are included in several shaders using format!, so line numbers in the compile errors can be mapped back to them
+fn shared_snippets() -> Vec<(&'static str, &'static str)> {
+    #[allow(unused_mut)]
+    let mut snippets = Vec::new();
+    #[cfg(feature = "phong-renderer")]
+    {
+        snippets.push(("light_shared.frag", include_str!("../phong/shaders/light_shared.frag")));
+        snippets.push(("deferred_light_shared.frag", include_str!("../phong/shaders/deferred_light_shared.frag")));
+        snippets.push(("deferred_objects_shared.frag", include_str!("../phong/shaders/deferred_objects_shared.frag")));
+    }
+    snippets
+}
+
+fn shader_compile_errors(log: &str, source: &str) -> Vec<ShaderCompileError> {
+    let header_line_count = crate::context::SHADER_HEADER.lines().count();
+
+    // The byte ranges of the shared snippets in the source
+    let mut ranges = Vec::new();
+    for (name, snippet) in shared_snippets() {
+        for (start, _) in source.match_indices(snippet) {
+            let mut end = start + snippet.len();
+            // Also skip the line break which separates a snippet ending with a line break from the next part
+            if snippet.ends_with('\n') && source[end..].starts_with('\n') {
+                end += 1;
+            }
+            ranges.push((start, end, name));
+        }
+    }
+    ranges.sort_by_key(|range| range.0);
+
+    // The snippet and line number within the snippet, or the line number within the rest of the source
+    // where each included snippet counts as one line, for each source line
+    let mut lines = Vec::new();
+    let mut offset = 0;
+    let mut rest_line = 0;
+    let mut previous_range = None;
+    for text in source.split('\n') {
+        let range = ranges.iter().find(|(start, end, _)| offset >= *start && offset < *end);
+        if let Some((start, _, name)) = range {
+            if previous_range != Some(*start) {
+                rest_line += 1;
+            }
+            lines.push((Some(name.to_string()), source[*start..offset].matches('\n').count() + 1, text));
+        } else {
+            rest_line += 1;
+            lines.push((None, rest_line, text));
+        }
+        previous_range = range.map(|(start, _, _)| *start);
+        offset += text.len() + 1;
+    }
+
+    log.lines().filter_map(|log_line| {
+        let (line, message) = parse_log_line(log_line)?;
+        let (snippet, line, source_line) = lines.get(line.checked_sub(header_line_count + 1)?)?;
+        Some(ShaderCompileError { snippet: snippet.clone(), line: *line, source_line: source_line.to_string(), message })
+    }).collect()
+}
+
+// Parses the line number and message from an error in the info log, warnings and other lines give None.
+// Supports the formats '0:12(5): error: message' (Mesa), '0(12) : error C0000: message' (Nvidia) and 'ERROR: 0:12: message' (most others).
+fn parse_log_line(log_line: &str) -> Option<(usize, String)> {
+    let log_line = log_line.trim();
+    if log_line.starts_with("WARNING:") {
+        return None;
+    }
+    let (has_prefix, rest) = log_line.strip_prefix("ERROR:").map(|rest| (true, rest.trim_start())).unwrap_or((false, log_line));
+
+    let number = |text: &str| -> Option<(usize, usize)> {
+        let length = text.find(|c: char| !c.is_ascii_digit()).unwrap_or(text.len());
+        text[..length].parse().ok().map(|number| (number, length))
+    };
+    let (_, length) = number(rest)?;
+    let rest = &rest[length..];
+    let (line, rest) = if let Some(rest) = rest.strip_prefix(':') {
+        let (line, length) = number(rest)?;
+        let rest = &rest[length..];
+        // Skip the column number
+        let rest = if rest.starts_with('(') { &rest[rest.find(')')? + 1..] } else { rest };
+        (line, rest)
+    } else if let Some(rest) = rest.strip_prefix('(') {
+        let (line, length) = number(rest)?;
+        (line, rest[length..].strip_prefix(')')?)
+    } else {
+        return None;
+    };
+    let message = rest.trim_start().trim_start_matches(':').trim();
+    if message.to_lowercase().starts_with("warning") {
+        return None;
+    }
+    Some((line, if has_prefix { format!("error: {}", message) } else { message.to_string() }))
+}
+
 impl Drop for Program {
     fn drop(&mut self) {
         self.context.delete_program(&self.id);
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_mesa_log_line() {
+        assert_eq!(parse_log_line("0:12(5): error: `x' undeclared"), Some((12, "error: `x' undeclared".to_string())));
+        assert_eq!(parse_log_line("0:3(10): warning: `y' used uninitialized"), None);
+    }
+
+    #[test]
+    fn parse_nvidia_log_line() {
+        assert_eq!(parse_log_line("0(12) : error C1008: undefined variable \"x\""), Some((12, "error C1008: undefined variable \"x\"".to_string())));
+        assert_eq!(parse_log_line("0(7) : warning C7050: \"y\" might be used before being initialized"), None);
+    }
+
+    #[test]
+    fn parse_generic_log_line() {
+        assert_eq!(parse_log_line("ERROR: 0:12: 'x' : undeclared identifier"), Some((12, "error: 'x' : undeclared identifier".to_string())));
+        assert_eq!(parse_log_line("WARNING: 0:4: extension not supported"), None);
+        assert_eq!(parse_log_line("ERROR: 2 compilation errors.  No code generated."), None);
+        assert_eq!(parse_log_line(""), None);
+    }
+
+    #[test]
+    fn map_compile_errors_to_source() {
+        let header_line_count = crate::context::SHADER_HEADER.lines().count();
+        let source = "uniform vec4 color;\nout vec4 outColor;\nvoid main() {\n    outColor = colour;\n}\n";
+        let log = format!("0:{}(16): error: `colour' undeclared\n0:{}(1): warning: unused\n", header_line_count + 4, header_line_count + 1);
+        let errors = shader_compile_errors(&log, source);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].snippet, None);
+        assert_eq!(errors[0].line, 4);
+        assert_eq!(errors[0].source_line, "    outColor = colour;");
+        assert_eq!(errors[0].message, "error: `colour' undeclared");
+        assert_eq!(errors[0].to_string(), "shader:4: error: `colour' undeclared\n    outColor = colour;");
+    }
+
+    #[cfg(feature = "phong-renderer")]
+    #[test]
+    fn map_compile_errors_to_snippets() {
+        let header_line_count = crate::context::SHADER_HEADER.lines().count();
+        let snippet = include_str!("../phong/shaders/light_shared.frag");
+        let snippet_line_count = snippet.split('\n').count();
+        // The snippet is included like the phong shaders do using format!
+        let source = format!("uniform vec4 color;\n{}\nout vec4 outColor;\nvoid main() {{\n    outColor = colour;\n}}\n", snippet);
+        let snippet_error_line = snippet.lines().position(|line| !line.trim().is_empty()).unwrap() + 1;
+        let log = format!("0({}) : error C0000: in snippet\n0({}) : error C1008: undefined variable \"colour\"\n",
+            header_line_count + 1 + snippet_error_line, header_line_count + 1 + snippet_line_count + 3);
+        let errors = shader_compile_errors(&log, &source);
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].snippet.as_deref(), Some("light_shared.frag"));
+        assert_eq!(errors[0].line, snippet_error_line);
+        assert_eq!(errors[0].source_line, snippet.lines().nth(snippet_error_line - 1).unwrap());
+        // The line number in the rest of the source counts the snippet as one line
+        assert_eq!(errors[1].snippet, None);
+        assert_eq!(errors[1].line, 5);
+        assert_eq!(errors[1].source_line, "    outColor = colour;");
+    }
+}
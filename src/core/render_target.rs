@@ -116,6 +116,25 @@ impl Screen {
         Ok(pixels)
     }
 
+    ///
+    /// Returns the RGBA color values from the screen as a list of bytes (one byte for each color channel).
+    /// Only available on desktop.
+    ///
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn read_color_rgba(context: &Context, viewport: Viewport) -> Result<Vec<u8>, Error>
+    {
+        let mut pixels = vec![0u8; viewport.width * viewport.height * 4];
+        context.bind_framebuffer(consts::READ_FRAMEBUFFER, None);
+        context.read_pixels_with_u8_data(viewport.x as u32,
+                                    viewport.y as u32,
+                                    viewport.width as u32,
+                                    viewport.height as u32,
+                                    consts::RGBA,
+                                    consts::UNSIGNED_BYTE,
+                                    &mut pixels);
+        Ok(pixels)
+    }
+
     ///
     /// Returns the depth values from the screen as a list of 32-bit floats.
     /// Only available on desktop.
@@ -340,6 +359,33 @@ impl<'a, 'b> RenderTarget<'a, 'b>
         Ok(())
     }
 
+    ///
+    /// Returns the RGBA color values from the color texture in this render target as a list of 32-bit floats (one float for each color channel),
+    /// for example to save a high dynamic range image using [Saver::save_pixels_f32](crate::Saver::save_pixels_f32).
+    /// Only available on desktop.
+    ///
+    /// # Errors
+    /// Will return an error if this render target is not constructed with a color texture.
+    ///
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn read_color_f32(&self, viewport: Viewport) -> Result<Vec<f32>, Error>
+    {
+        if self.color_texture.is_none() {
+            Err(Error::FailedToCopyFromRenderTarget {message: "Cannot read color when the render target does not have a color texture.".to_owned()})?;
+        }
+        self.bind()?;
+        let mut pixels = vec![0f32; viewport.width * viewport.height * 4];
+        self.context.bind_framebuffer(consts::READ_FRAMEBUFFER, Some(&self.id));
+        self.context.read_pixels_with_f32_data(viewport.x as u32,
+                                    viewport.y as u32,
+                                    viewport.width as u32,
+                                    viewport.height as u32,
+                                    consts::RGBA,
+                                    consts::FLOAT,
+                                    &mut pixels);
+        Ok(pixels)
+    }
+
     fn bind(&self) -> Result<(), Error> {
         self.context.bind_framebuffer(consts::DRAW_FRAMEBUFFER, Some(&self.id));
         if let Some(tex) = self.color_texture {
@@ -1,6 +1,9 @@
 use crate::core::Error;
 use crate::context::{Context, consts};
 use crate::cpu_texture::*;
+use std::rc::{Rc, Weak};
+use std::cell::RefCell;
+use std::collections::HashMap;
 
 ///
 /// A texture that can be sampled in a fragment shader (see [use_texture](crate::Program::use_texture)).
@@ -103,6 +106,78 @@ impl Drop for Texture2D
     }
 }
 
+///
+/// A cache of [2D textures](crate::Texture2D) which makes sure that only one GPU texture is created for each shared CPU texture,
+/// for example the CPU textures returned by [Loaded::shared_image](crate::Loaded::shared_image), which are cached by path.
+/// Use this cache to control when the textures are deleted, otherwise [PhongMaterial::new](crate::PhongMaterial::new)
+/// already shares the textures using a cache for the current thread.
+///
+#[derive(Default)]
+pub struct TextureCache {
+    // The cache keeps the CPU texture alive, so the address can not be reused by another CPU texture
+    textures: HashMap<*const CPUTexture<u8>, CachedTexture>
+}
+
+type CachedTexture = (Rc<CPUTexture<u8>>, Rc<Texture2D>);
+
+type SharedTextures = HashMap<*const CPUTexture<u8>, (Weak<CPUTexture<u8>>, Weak<Texture2D>)>;
+
+thread_local! {
+    // Only weak references are kept, so the textures are deleted when they are no longer in use
+    static SHARED_TEXTURES: RefCell<SharedTextures> = RefCell::new(HashMap::new());
+}
+
+impl TextureCache
+{
+    pub fn new() -> Self
+    {
+        Self::default()
+    }
+
+    ///
+    /// Returns the 2D texture created from the given CPU texture.
+    /// The texture is created the first time and the same texture is returned for subsequent calls with the same shared CPU texture.
+    ///
+    pub fn texture(&mut self, context: &Context, cpu_texture: &Rc<CPUTexture<u8>>) -> Result<Rc<Texture2D>, Error>
+    {
+        if let Some((_, texture)) = self.textures.get(&Rc::as_ptr(cpu_texture)) {
+            return Ok(texture.clone());
+        }
+        let texture = Rc::new(Texture2D::new_with_u8(context, cpu_texture)?);
+        self.textures.insert(Rc::as_ptr(cpu_texture), (cpu_texture.clone(), texture.clone()));
+        Ok(texture)
+    }
+
+    ///
+    /// Returns the 2D texture created from the given CPU texture using a cache for the current thread.
+    /// The same texture is returned for the same shared CPU texture and context as long as the texture is in use.
+    ///
+    pub(crate) fn shared_texture(context: &Context, cpu_texture: &Rc<CPUTexture<u8>>) -> Result<Rc<Texture2D>, Error>
+    {
+        SHARED_TEXTURES.with(|textures| {
+            let mut textures = textures.borrow_mut();
+            // A weak reference keeps the address of the CPU texture from being reused, even after it is dropped
+            if let Some(texture) = textures.get(&Rc::as_ptr(cpu_texture)).and_then(|(_, texture)| texture.upgrade()) {
+                if Rc::ptr_eq(&texture.context, context) {
+                    return Ok(texture);
+                }
+            }
+            let texture = Rc::new(Texture2D::new_with_u8(context, cpu_texture)?);
+            textures.retain(|_, (cpu_texture, texture)| cpu_texture.strong_count() > 0 && texture.strong_count() > 0);
+            textures.insert(Rc::as_ptr(cpu_texture), (Rc::downgrade(cpu_texture), Rc::downgrade(&texture)));
+            Ok(texture)
+        })
+    }
+
+    ///
+    /// Removes all textures from the cache. The textures are deleted when they are no longer in use.
+    ///
+    pub fn clear(&mut self)
+    {
+        self.textures.clear();
+    }
+}
+
 ///
 /// A 2D texture that can be rendered into using a [RenderTarget](crate::RenderTarget).
 ///
@@ -239,6 +314,13 @@ impl TextureCubeMap
         Ok(texture)
     }
 
+    pub fn new_with_f32(context: &Context, cpu_texture: &CPUTexture<f32>) -> Result<Self, Error>
+    {
+        let mut texture = Self::new(context, cpu_texture)?;
+        texture.fill_with_f32(&cpu_texture.data)?;
+        Ok(texture)
+    }
+
     // data contains 6 images in the following order; right, left, top, bottom, front, back
     pub fn fill_with_u8(&mut self, data: &[u8]) -> Result<(), Error>
     {
@@ -256,6 +338,23 @@ impl TextureCubeMap
         Ok(())
     }
 
+    // data contains 6 images in the following order; right, left, top, bottom, front, back
+    pub fn fill_with_f32(&mut self, data: &[f32]) -> Result<(), Error>
+    {
+        check_f32_format(self.format)?;
+        let offset = data.len()/6;
+        check_data_length(self.width, self.height, 1, self.format, offset)?;
+        self.context.bind_texture(consts::TEXTURE_CUBE_MAP, &self.id);
+        for i in 0..6 {
+            self.context.tex_sub_image_2d_with_f32_data(consts::TEXTURE_CUBE_MAP_POSITIVE_X + i as u32, 0, 0, 0,
+                                                        self.width as u32, self.height as u32,
+                                                        format_from(self.format), consts::FLOAT,
+                                                        &data[i*offset..(i+1)*offset]);
+        }
+        self.generate_mip_maps();
+        Ok(())
+    }
+
     fn new<T>(context: &Context, cpu_texture: &CPUTexture<T>) -> Result<TextureCubeMap, Error>
     {
         let id = generate(context)?;
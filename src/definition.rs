@@ -10,6 +10,11 @@ pub mod cpu_mesh;
 #[doc(inline)]
 pub use crate::cpu_mesh::*;
 
+#[doc(hidden)]
+pub mod mesh_validation;
+#[doc(inline)]
+pub use crate::mesh_validation::*;
+
 #[doc(hidden)]
 pub mod cpu_material;
 #[doc(inline)]
@@ -18,4 +23,14 @@ pub use crate::cpu_material::*;
 #[doc(hidden)]
 pub mod cpu_texture;
 #[doc(inline)]
-pub use crate::cpu_texture::*;
\ No newline at end of file
+pub use crate::cpu_texture::*;
+
+#[doc(hidden)]
+pub mod cpu_camera;
+#[doc(inline)]
+pub use crate::cpu_camera::*;
+
+#[doc(hidden)]
+pub mod cpu_light;
+#[doc(inline)]
+pub use crate::cpu_light::*;
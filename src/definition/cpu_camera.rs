@@ -0,0 +1,44 @@
+use crate::math::*;
+
+///
+/// The projection of a [CPUCamera](crate::CPUCamera).
+/// The aspect ratio of a perspective projection is not stored since it depends on the viewport.
+///
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde-support", derive(serde::Serialize, serde::Deserialize))]
+pub enum CPUProjection {
+    Perspective {field_of_view_y: Degrees, z_near: f32, z_far: f32},
+    Orthographic {width: f32, height: f32, depth: f32}
+}
+
+///
+/// A CPU-side version of a [camera](crate::Camera).
+/// Can be constructed manually or loaded via [io](crate::io).
+///
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde-support", derive(serde::Serialize, serde::Deserialize))]
+pub struct CPUCamera {
+    pub name: String,
+    pub position: Vec3,
+    pub target: Vec3,
+    pub up: Vec3,
+    pub projection: CPUProjection
+}
+
+#[cfg(all(test, feature = "serde-support", feature = "3d-io"))]
+mod tests {
+    use super::*;
+    use crate::definition::*;
+
+    #[test]
+    fn serialize_camera_and_light() {
+        let camera = CPUCamera {name: "camera".to_string(), position: vec3(0.0, 1.0, 5.0), target: vec3(0.0, 0.0, 0.0), up: vec3(0.0, 1.0, 0.0),
+            projection: CPUProjection::Perspective {field_of_view_y: degrees(45.0), z_near: 0.1, z_far: 100.0}};
+        let bytes = bincode::serialize(&camera).unwrap();
+        assert_eq!(bincode::deserialize::<CPUCamera>(&bytes).unwrap(), camera);
+
+        let light = CPULight::Directional {intensity: 0.5, color: vec3(1.0, 1.0, 1.0), direction: vec3(0.0, -1.0, 0.0)};
+        let bytes = bincode::serialize(&light).unwrap();
+        assert_eq!(bincode::deserialize::<CPULight>(&bytes).unwrap(), light);
+    }
+}
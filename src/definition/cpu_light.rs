@@ -0,0 +1,16 @@
+use crate::math::*;
+
+///
+/// A CPU-side version of a light (for example a [directional light](crate::DirectionalLight)).
+/// Can be constructed manually or loaded via [io](crate::io).
+///
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde-support", derive(serde::Serialize, serde::Deserialize))]
+pub enum CPULight {
+    Ambient {intensity: f32, color: Vec3},
+    Directional {intensity: f32, color: Vec3, direction: Vec3},
+    Point {intensity: f32, color: Vec3, position: Vec3,
+        attenuation_constant: f32, attenuation_linear: f32, attenuation_exponential: f32},
+    Spot {intensity: f32, color: Vec3, position: Vec3, direction: Vec3, cutoff: f32,
+        attenuation_constant: f32, attenuation_linear: f32, attenuation_exponential: f32}
+}
@@ -1,16 +1,27 @@
 use crate::definition::*;
+use std::rc::Rc;
 
 ///
 /// A CPU-side version of a material (for example [phong material](crate::PhongMaterial)).
 /// Can be constructed manually or loaded via [io](crate::io).
+/// When serialized using the `serde-support` feature, images shared between materials are serialized once for each material.
 ///
+#[cfg_attr(feature = "serde-support", derive(serde::Serialize, serde::Deserialize))]
 pub struct CPUMaterial {
     pub name: String,
     pub color: Option<(f32, f32, f32, f32)>,
-    pub texture_image: Option<CPUTexture<u8>>,
+    pub texture_image: Option<Rc<CPUTexture<u8>>>,
     pub diffuse_intensity: Option<f32>,
     pub specular_intensity: Option<f32>,
-    pub specular_power: Option<f32>
+    pub specular_power: Option<f32>,
+    /// The emitted color, which is independent of the lighting.
+    pub emissive_color: Option<(f32, f32, f32)>,
+    /// A texture image scaling the specular intensity.
+    pub specular_image: Option<Rc<CPUTexture<u8>>>,
+    /// A normal map or bump (height) map.
+    pub bump_image: Option<Rc<CPUTexture<u8>>>,
+    /// A texture image defining the opacity, 0 is fully transparent and 255 is fully opaque.
+    pub opacity_image: Option<Rc<CPUTexture<u8>>>
 }
 
 impl Default for CPUMaterial {
@@ -21,7 +32,11 @@ impl Default for CPUMaterial {
             texture_image: None,
             diffuse_intensity: Some(0.5),
             specular_intensity: Some(0.2),
-            specular_power: Some(6.0)
+            specular_power: Some(6.0),
+            emissive_color: None,
+            specular_image: None,
+            bump_image: None,
+            opacity_image: None
         }
      }
 }
\ No newline at end of file
@@ -2,17 +2,57 @@
 use crate::math::*;
 
 ///
-/// A CPU-side version of a triangle mesh (for example [Mesh](crate::Mesh)).
+/// The type of primitives a [CPUMesh](crate::CPUMesh) consists of.
+///
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-support", derive(serde::Serialize, serde::Deserialize))]
+pub enum PrimitiveType {
+    /// Every three vertices define a triangle.
+    Triangles,
+    /// Every two vertices define a line segment.
+    Lines,
+    /// Every vertex defines a point.
+    Points
+}
+
+impl PrimitiveType {
+    ///
+    /// Returns the number of vertices which define one primitive, ie. 3 for triangles, 2 for lines and 1 for points.
+    ///
+    pub fn vertex_count(&self) -> usize {
+        match self {
+            PrimitiveType::Triangles => 3,
+            PrimitiveType::Lines => 2,
+            PrimitiveType::Points => 1
+        }
+    }
+}
+
+impl Default for PrimitiveType {
+    fn default() -> Self {
+        PrimitiveType::Triangles
+    }
+}
+
+///
+/// A CPU-side version of a mesh (for example [Mesh](crate::Mesh)), usually a triangle mesh but it can also consist of lines or points.
 /// Can be constructed manually or loaded via [io](crate::io)
 /// or via the utility functions for generating simple triangle meshes.
 ///
 #[derive(Default, Debug)]
+#[cfg_attr(feature = "serde-support", derive(serde::Serialize, serde::Deserialize))]
 pub struct CPUMesh {
     pub name: String,
     pub material_name: Option<String>,
+    /// The type of primitives, ie. how the vertices (or the indices if specified) are combined into triangles, lines or points.
+    #[cfg_attr(feature = "serde-support", serde(default))]
+    pub primitive_type: PrimitiveType,
     pub positions: Vec<f32>,
     pub indices: Option<Vec<u32>>,
     pub normals: Option<Vec<f32>>,
+    /// The tangents (four values per vertex) in the direction of increasing u coordinate, see [compute_tangents](Self::compute_tangents).
+    /// The fourth value is the handedness (1 or -1) so the bitangent is `handedness * cross(normal, tangent)`.
+    pub tangents: Option<Vec<f32>>,
     pub uvs: Option<Vec<f32>>,
     pub colors: Option<Vec<u8>>
 }
@@ -164,8 +204,12 @@ impl CPUMesh {
     ///
     /// Computes the per vertex normals and updates the normals of the mesh.
     /// It will override the current normals if they already exist.
+    /// Does nothing for a mesh with lines or points, since the normals can only be computed from triangles.
     ///
     pub fn compute_normals(&mut self) {
+        if self.primitive_type != PrimitiveType::Triangles {
+            return;
+        }
         if let Some(ref ind) = self.indices {
             self.normals = Some(compute_normals_with_indices(ind, &self.positions));
         } else {
@@ -173,6 +217,72 @@ impl CPUMesh {
         }
     }
 
+    ///
+    /// Computes the per vertex tangents from the positions, normals and uv coordinates and updates the tangents of the mesh.
+    /// It will override the current tangents if they already exist and the normals are computed if they do not exist.
+    /// The tangent of a vertex is the average of the tangents of the faces sharing the vertex, projected onto the tangent plane of the vertex
+    /// and weighted by the angle of each face at the vertex.
+    /// Vertices shared between faces with mirrored uv coordinates, ie. at a mirror seam, are split into two vertices, one for each side of the seam,
+    /// so both sides get the correct handedness. Vertices at a uv seam are already separate vertices, since they have different uv coordinates.
+    /// Note that the tangents are similar to, but not identical with, the tangents computed by MikkTSpace, so normal maps baked with
+    /// MikkTSpace tangents might show small artifacts.
+    ///
+    /// # Errors
+    /// Will return an error if the mesh is not a triangle mesh, does not have indices or uv coordinates, if the number of normals, uv coordinates or colors
+    /// does not match the number of positions or if an index refers to a vertex which does not exist.
+    ///
+    pub fn compute_tangents(&mut self) -> Result<(), crate::core::Error> {
+        if self.primitive_type != PrimitiveType::Triangles {
+            Err(crate::core::Error::FailedToComputeTangents {message: format!("The mesh {} is not a triangle mesh.", self.name)})?;
+        }
+        if self.indices.is_none() {
+            Err(crate::core::Error::FailedToComputeTangents {message: format!("The mesh {} does not have indices.", self.name)})?;
+        }
+        if self.uvs.is_none() {
+            Err(crate::core::Error::FailedToComputeTangents {message: format!("The mesh {} does not have uv coordinates.", self.name)})?;
+        }
+        let error = |message: String| crate::core::Error::FailedToComputeTangents {message: format!("The mesh {} {}.", self.name, message)};
+        let vertex_count = self.positions.len() / 3;
+        let uv_count = self.uvs.as_ref().unwrap().len();
+        if uv_count != vertex_count * 2 {
+            Err(error(format!("has {} uv values but should have {}", uv_count, vertex_count * 2)))?;
+        }
+        if let Some(normal_count) = self.normals.as_ref().map(|normals| normals.len()) {
+            if normal_count != vertex_count * 3 {
+                Err(error(format!("has {} normal values but should have {}", normal_count, vertex_count * 3)))?;
+            }
+        }
+        if let Some(color_count) = self.colors.as_ref().map(|colors| colors.len()) {
+            if color_count != vertex_count * 4 {
+                Err(error(format!("has {} color values but should have {}", color_count, vertex_count * 4)))?;
+            }
+        }
+        if let Some(index) = self.indices.as_ref().unwrap().iter().find(|i| **i as usize >= vertex_count) {
+            Err(error(format!("has the index {} but only {} vertices", index, vertex_count)))?;
+        }
+        if self.normals.is_none() {
+            self.compute_normals();
+        }
+
+        let split_vertices = split_mirrored_vertices(self.indices.as_mut().unwrap(), self.uvs.as_ref().unwrap(), vertex_count);
+        if !split_vertices.is_empty() {
+            fn append_copies<T: Copy>(values: &mut Vec<T>, size: usize, vertices: &[usize]) {
+                for vertex in vertices {
+                    values.extend_from_within(vertex * size..(vertex + 1) * size);
+                }
+            }
+            append_copies(&mut self.positions, 3, &split_vertices);
+            append_copies(self.normals.as_mut().unwrap(), 3, &split_vertices);
+            append_copies(self.uvs.as_mut().unwrap(), 2, &split_vertices);
+            if let Some(ref mut colors) = self.colors {
+                append_copies(colors, 4, &split_vertices);
+            }
+        }
+        self.tangents = Some(compute_tangents(self.indices.as_ref().unwrap(), &self.positions,
+                                              self.normals.as_ref().unwrap(), self.uvs.as_ref().unwrap()));
+        Ok(())
+    }
+
     ///
     /// Computes the axis aligned bounding box of the mesh.
     ///
@@ -182,7 +292,7 @@ impl CPUMesh {
 }
 
 fn compute_normals_with_indices(indices: &[u32], positions: &[f32]) -> Vec<f32> {
-    let mut normals = vec![0.0f32; positions.len() * 3];
+    let mut normals = vec![0.0f32; positions.len()];
     for face in 0..indices.len()/3 {
         let index0 = indices[face*3] as usize;
         let p0 = vec3(positions[index0*3], positions[index0*3+1], positions[index0*3+2]);
@@ -242,4 +352,161 @@ fn compute_normals(positions: &[f32]) -> Vec<f32> {
         normals[3*i+2] = normal.z;
     }
     normals
-}
\ No newline at end of file
+}
+
+// Makes sure that all faces sharing a vertex have the same handedness of the uv coordinates, by replacing the vertex with a copy in the faces
+// with the opposite handedness to the first face using the vertex. Returns the vertices which are copied, in the order of the new vertices.
+fn split_mirrored_vertices(indices: &mut [u32], uvs: &[f32], vertex_count: usize) -> Vec<usize> {
+    let uv = |i: u32| vec2(uvs[i as usize * 2], uvs[i as usize * 2 + 1]);
+    let mut handedness = vec![0.0f32; vertex_count];
+    let mut copies: Vec<Option<u32>> = vec![None; vertex_count];
+    let mut split_vertices = Vec::new();
+    for face in indices.chunks_exact_mut(3) {
+        let (t1, t2) = (uv(face[1]) - uv(face[0]), uv(face[2]) - uv(face[0]));
+        let signed_area = t1.x * t2.y - t1.y * t2.x;
+        if signed_area == 0.0 {
+            // No uv area, so the face does not contribute to the tangents
+            continue;
+        }
+        let face_handedness = signed_area.signum();
+        for index in face.iter_mut() {
+            let vertex = *index as usize;
+            if handedness[vertex] == 0.0 {
+                handedness[vertex] = face_handedness;
+            } else if handedness[vertex] != face_handedness {
+                *index = *copies[vertex].get_or_insert_with(|| {
+                    split_vertices.push(vertex);
+                    (vertex_count + split_vertices.len() - 1) as u32
+                });
+            }
+        }
+    }
+    split_vertices
+}
+
+fn compute_tangents(indices: &[u32], positions: &[f32], normals: &[f32], uvs: &[f32]) -> Vec<f32> {
+    let vertex_count = positions.len() / 3;
+    let position = |i: usize| vec3(positions[i*3], positions[i*3+1], positions[i*3+2]);
+    let normal = |i: usize| vec3(normals[i*3], normals[i*3+1], normals[i*3+2]);
+    let uv = |i: usize| vec2(uvs[i*2], uvs[i*2+1]);
+    // Projects the vector onto the tangent plane of the normal and normalizes it
+    let project = |n: Vec3, v: Vec3| {
+        let v = v - n * n.dot(v);
+        if v.magnitude2() > 0.0 { v.normalize() } else { v }
+    };
+
+    let mut tangents = vec![vec3(0.0, 0.0, 0.0); vertex_count];
+    let mut bitangents = vec![vec3(0.0, 0.0, 0.0); vertex_count];
+    for face in indices.chunks(3) {
+        if face.len() < 3 {
+            break;
+        }
+        let [i0, i1, i2] = [face[0] as usize, face[1] as usize, face[2] as usize];
+        let (d1, d2) = (position(i1) - position(i0), position(i2) - position(i0));
+        let (t1, t2) = (uv(i1) - uv(i0), uv(i2) - uv(i0));
+        let signed_area = t1.x * t2.y - t1.y * t2.x;
+        let tangent = d1 * t2.y - d2 * t1.y;
+        let bitangent = d2 * t1.x - d1 * t2.x;
+        let (tangent, bitangent) = if signed_area < 0.0 { (-tangent, -bitangent) } else { (tangent, bitangent) };
+
+        for corner in 0..3 {
+            let (i, prev, next) = (face[corner] as usize, face[(corner + 2) % 3] as usize, face[(corner + 1) % 3] as usize);
+            let n = normal(i);
+            let edge0 = project(n, position(next) - position(i));
+            let edge1 = project(n, position(prev) - position(i));
+            let angle = edge0.dot(edge1).clamp(-1.0, 1.0).acos();
+            tangents[i] += project(n, tangent) * angle;
+            bitangents[i] += project(n, bitangent) * angle;
+        }
+    }
+
+    let mut result = Vec::with_capacity(vertex_count * 4);
+    for i in 0..vertex_count {
+        let n = normal(i);
+        let mut tangent = project(n, tangents[i]);
+        if tangent.magnitude2() == 0.0 {
+            // No uv gradient, so choose any tangent perpendicular to the normal
+            let axis = if n.x.abs() < 0.9 { vec3(1.0, 0.0, 0.0) } else { vec3(0.0, 1.0, 0.0) };
+            tangent = project(n, axis);
+        }
+        let handedness = if n.cross(tangent).dot(bitangents[i]) < 0.0 { -1.0 } else { 1.0 };
+        result.extend_from_slice(&[tangent.x, tangent.y, tangent.z, handedness]);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quad() -> CPUMesh {
+        CPUMesh {
+            positions: vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 1.0, 0.0],
+            indices: Some(vec![0, 1, 2, 0, 2, 3]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn compute_normals_with_indices() {
+        let mut cpu_mesh = quad();
+        cpu_mesh.compute_normals();
+        // One normal for each vertex
+        assert_eq!(cpu_mesh.normals, Some(vec![0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0]));
+    }
+
+    #[test]
+    fn compute_tangents() {
+        let mut cpu_mesh = CPUMesh {uvs: Some(vec![0.0, 0.0, 1.0, 0.0, 1.0, 1.0, 0.0, 1.0]), ..quad()};
+        cpu_mesh.compute_tangents().unwrap();
+        // The tangents point in the direction of increasing u and the bitangents, cross(normal, tangent), in the direction of increasing v
+        assert_eq!(cpu_mesh.tangents, Some([1.0, 0.0, 0.0, 1.0].repeat(4)));
+
+        // Mirrored uv coordinates
+        let mut cpu_mesh = CPUMesh {uvs: Some(vec![1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0]), ..quad()};
+        cpu_mesh.compute_tangents().unwrap();
+        assert_eq!(cpu_mesh.tangents, Some([-1.0, 0.0, 0.0, -1.0].repeat(4)));
+    }
+
+    #[test]
+    fn compute_tangents_at_mirror_seam() {
+        // Two quads sharing the edge from (1, 0) to (1, 1), where the u coordinate is mirrored in the right quad
+        let mut cpu_mesh = CPUMesh {
+            positions: vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 2.0, 0.0, 0.0, 0.0, 1.0, 0.0, 1.0, 1.0, 0.0, 2.0, 1.0, 0.0],
+            indices: Some(vec![0, 1, 4, 0, 4, 3, 1, 2, 5, 1, 5, 4]),
+            uvs: Some(vec![0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 0.0, 1.0]),
+            ..Default::default()
+        };
+        cpu_mesh.compute_tangents().unwrap();
+
+        // The two vertices on the seam are split, so the right quad refers to copies of them
+        assert_eq!(cpu_mesh.indices, Some(vec![0, 1, 4, 0, 4, 3, 6, 2, 5, 6, 5, 7]));
+        assert_eq!(&cpu_mesh.positions[18..], &[1.0, 0.0, 0.0, 1.0, 1.0, 0.0]);
+        assert_eq!(&cpu_mesh.uvs.as_ref().unwrap()[12..], &[1.0, 0.0, 1.0, 1.0]);
+        assert_eq!(cpu_mesh.normals.as_ref().unwrap().len(), 24);
+        let tangents = cpu_mesh.tangents.unwrap();
+        for (vertex, tangent) in tangents.chunks(4).enumerate() {
+            if [0, 1, 3, 4].contains(&vertex) {
+                assert_eq!(tangent, &[1.0, 0.0, 0.0, 1.0]);
+            } else {
+                assert_eq!(tangent, &[-1.0, 0.0, 0.0, -1.0]);
+            }
+        }
+    }
+
+    #[test]
+    fn compute_tangents_invalid() {
+        let uvs = Some(vec![0.0, 0.0, 1.0, 0.0, 1.0, 1.0, 0.0, 1.0]);
+        for mut cpu_mesh in [
+            CPUMesh {uvs: Some(vec![0.0, 0.0, 1.0, 0.0]), ..quad()},
+            CPUMesh {uvs: uvs.clone(), normals: Some(vec![0.0, 0.0, 1.0]), ..quad()},
+            CPUMesh {uvs: uvs.clone(), indices: Some(vec![0, 1, 2, 0, 2, 4]), ..quad()}
+        ] {
+            match cpu_mesh.compute_tangents() {
+                Err(crate::core::Error::FailedToComputeTangents {..}) => {},
+                result => panic!("Expected an error, got {:?}", result)
+            }
+            assert!(cpu_mesh.tangents.is_none());
+        }
+    }
+}
@@ -3,6 +3,7 @@
 /// Possible modes of interpolation which determines the texture output between texture pixels.
 ///
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde-support", derive(serde::Serialize, serde::Deserialize))]
 pub enum Interpolation {
     Nearest,
     Linear
@@ -13,6 +14,7 @@ pub enum Interpolation {
 /// [0..1] uv coordinate range.
 ///
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde-support", derive(serde::Serialize, serde::Deserialize))]
 pub enum Wrapping {
     Repeat,
     MirroredRepeat,
@@ -23,6 +25,7 @@ pub enum Wrapping {
 /// Possible formats for pixels in a texture.
 ///
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "serde-support", derive(serde::Serialize, serde::Deserialize))]
 pub enum Format {
     R8,
     R32F,
@@ -38,6 +41,7 @@ pub enum Format {
 /// A CPU-side version of a texture (for example [2D texture](crate::Texture2D).
 /// Can be constructed manually or loaded via [io](crate::io).
 ///
+#[cfg_attr(feature = "serde-support", derive(serde::Serialize, serde::Deserialize))]
 pub struct CPUTexture<T> {
     pub data: Vec<T>,
     pub width: usize,
@@ -68,4 +72,156 @@ impl Default for CPUTexture<u8> {
             wrap_r: Wrapping::Repeat
         }
     }
-}
\ No newline at end of file
+}
+
+impl Default for CPUTexture<f32> {
+    fn default() -> Self {
+        Self {
+            data: [1.0f32, 1.0, 0.0, 1.0].into(),
+            width: 1,
+            height: 1,
+            depth: 1,
+            format: Format::RGBA32F,
+            min_filter: Interpolation::Linear,
+            mag_filter: Interpolation::Linear,
+            mip_map_filter: Some(Interpolation::Linear),
+            wrap_s: Wrapping::Repeat,
+            wrap_t: Wrapping::Repeat,
+            wrap_r: Wrapping::Repeat
+        }
+    }
+}
+
+impl CPUTexture<u8> {
+    ///
+    /// Converts this texture, which should contain an equirectangular panorama, into the six faces of a cube map
+    /// with the given width and height, in the order right, left, top, bottom, front and back.
+    /// The center of the panorama is mapped to the back face, which is in the negative z direction.
+    /// An empty panorama gives an empty cube map, ie. with faces of size zero.
+    /// The result can be used to create a [TextureCubeMap](crate::TextureCubeMap) or a [Skybox](crate::Skybox).
+    ///
+    pub fn cube_map_from_equirectangular(&self, face_size: usize) -> CPUTexture<u8> {
+        let data = equirectangular_to_cube_map(self.width, self.height, &self.data.iter().map(|v| *v as f32).collect::<Vec<_>>(), face_size);
+        CPUTexture {data: data.iter().map(|v| v.round().clamp(0.0, 255.0) as u8).collect(), ..cube_map_texture(self, face_size)}
+    }
+}
+
+impl CPUTexture<f32> {
+    ///
+    /// Converts this texture, which should contain an equirectangular panorama, into the six faces of a cube map
+    /// with the given width and height, in the order right, left, top, bottom, front and back.
+    /// The center of the panorama is mapped to the back face, which is in the negative z direction.
+    /// An empty panorama gives an empty cube map, ie. with faces of size zero.
+    /// The result can be used to create a [TextureCubeMap](crate::TextureCubeMap).
+    ///
+    pub fn cube_map_from_equirectangular(&self, face_size: usize) -> CPUTexture<f32> {
+        CPUTexture {data: equirectangular_to_cube_map(self.width, self.height, &self.data, face_size), ..cube_map_texture(self, face_size)}
+    }
+}
+
+fn cube_map_texture<T, S>(texture: &CPUTexture<S>, face_size: usize) -> CPUTexture<T> {
+    let face_size = if texture.width == 0 || texture.height == 0 { 0 } else { face_size };
+    CPUTexture {
+        data: Vec::new(),
+        width: face_size,
+        height: face_size,
+        depth: 1,
+        format: texture.format,
+        min_filter: texture.min_filter,
+        mag_filter: texture.mag_filter,
+        mip_map_filter: texture.mip_map_filter,
+        wrap_s: Wrapping::ClampToEdge,
+        wrap_t: Wrapping::ClampToEdge,
+        wrap_r: Wrapping::ClampToEdge
+    }
+}
+
+fn equirectangular_to_cube_map(width: usize, height: usize, data: &[f32], face_size: usize) -> Vec<f32> {
+    use std::f32::consts::PI;
+    if width == 0 || height == 0 {
+        return Vec::new();
+    }
+    let channels = data.len() / (width * height).max(1);
+    let texel = |x: usize, y: usize, c: usize| data[(y * width + x) * channels + c];
+
+    let mut result = Vec::with_capacity(6 * face_size * face_size * channels);
+    for face in 0..6 {
+        for row in 0..face_size {
+            for col in 0..face_size {
+                // The direction through the center of the cube map texel as defined in the OpenGL specification
+                let s = 2.0 * (col as f32 + 0.5) / face_size as f32 - 1.0;
+                let t = 2.0 * (row as f32 + 0.5) / face_size as f32 - 1.0;
+                let (x, y, z) = match face {
+                    0 => (1.0, -t, -s),
+                    1 => (-1.0, -t, s),
+                    2 => (s, 1.0, t),
+                    3 => (s, -1.0, -t),
+                    4 => (s, -t, 1.0),
+                    _ => (-s, -t, -1.0)
+                };
+                let length = (x * x + y * y + z * z).sqrt();
+
+                // Bilinear lookup in the panorama which wraps horizontally
+                let u = (0.5 + x.atan2(-z) / (2.0 * PI)) * width as f32 - 0.5;
+                let v = ((y / length).clamp(-1.0, 1.0).acos() / PI * height as f32 - 0.5).clamp(0.0, (height - 1) as f32);
+                let (u0, v0) = (u.floor(), v.floor());
+                let (fu, fv) = (u - u0, v - v0);
+                let x0 = (u0 as isize).rem_euclid(width as isize) as usize;
+                let x1 = (x0 + 1) % width;
+                let y0 = v0 as usize;
+                let y1 = (y0 + 1).min(height - 1);
+                for c in 0..channels {
+                    let top = texel(x0, y0, c) * (1.0 - fu) + texel(x1, y0, c) * fu;
+                    let bottom = texel(x0, y1, c) * (1.0 - fu) + texel(x1, y1, c) * fu;
+                    result.push(top * (1.0 - fv) + bottom * fv);
+                }
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn panorama(width: usize, height: usize, data: Vec<u8>) -> CPUTexture<u8> {
+        CPUTexture {data, width, height, format: Format::R8, ..Default::default()}
+    }
+
+    #[test]
+    fn single_color_panorama() {
+        let cube_map = panorama(16, 8, vec![200; 16 * 8]).cube_map_from_equirectangular(4);
+        assert_eq!((cube_map.width, cube_map.height), (4, 4));
+        assert_eq!(cube_map.data, vec![200; 6 * 4 * 4]);
+
+        let texture = CPUTexture {data: [0.25f32, 0.5, 1.0].repeat(8 * 4), width: 8, height: 4, format: Format::RGB32F, ..Default::default()};
+        let cube_map = texture.cube_map_from_equirectangular(3);
+        assert!(cube_map.data.chunks(3).all(|texel| texel.iter().zip([0.25, 0.5, 1.0].iter()).all(|(a, b)| (a - b).abs() < 1e-6)));
+        assert_eq!(cube_map.data.len(), 6 * 3 * 3 * 3);
+    }
+
+    #[test]
+    fn face_orientation() {
+        // The top and bottom rows are the up and down directions, and the columns from the left are the front, left, back, right and front directions
+        let middle = [40, 50, 50, 60, 60, 70, 70, 40];
+        let data = [[10; 8], middle, middle, [20; 8]].concat();
+        let cube_map = panorama(8, 4, data).cube_map_from_equirectangular(1);
+        // The faces are in the order right, left, top, bottom, front and back
+        assert_eq!(cube_map.data, vec![70, 50, 10, 20, 40, 60]);
+
+        // The upper half of the panorama is 0 and the lower half is 100, and the left half adds 0 and the right half adds 10
+        let data = (0..4).flat_map(|row| (0..8).map(move |col| if row < 2 { 0 } else { 100 } + if col < 4 { 0 } else { 10 })).collect();
+        let cube_map = panorama(8, 4, data).cube_map_from_equirectangular(2);
+        // The first row of the front and back faces is up, and their first column is in the negative and positive x direction respectively
+        assert_eq!(cube_map.data[16..20], [0, 10, 100, 110]);
+        assert_eq!(cube_map.data[20..24], [10, 0, 110, 100]);
+    }
+
+    #[test]
+    fn empty_panorama() {
+        let cube_map = panorama(0, 0, Vec::new()).cube_map_from_equirectangular(4);
+        assert_eq!((cube_map.width, cube_map.height), (0, 0));
+        assert!(cube_map.data.is_empty());
+    }
+}
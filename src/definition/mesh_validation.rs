@@ -0,0 +1,382 @@
+use crate::math::*;
+use crate::definition::*;
+use std::collections::HashMap;
+
+///
+/// An issue found when [validating](crate::CPUMesh::validate) a mesh.
+///
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde-support", derive(serde::Serialize, serde::Deserialize))]
+pub enum MeshIssue {
+    /// The number of position values is not a multiple of three.
+    PositionCount {count: usize},
+    /// The number of indices is not a multiple of the number of vertices of a primitive, ie. three for triangles and two for lines.
+    IndexCount {count: usize, primitive_type: PrimitiveType},
+    /// A primitive (a triangle, line or point) refers to a vertex which does not exist.
+    IndexOutOfRange {primitive: usize, index: u32},
+    /// The number of values of an attribute (normals, uvs, colors or tangents) does not match the number of vertices.
+    AttributeLength {attribute: String, expected: usize, actual: usize},
+    /// An attribute value of a vertex is NaN or infinite.
+    NotFinite {attribute: String, vertex: usize},
+    /// A triangle has no area, so it is never visible.
+    DegenerateTriangle {triangle: usize},
+    /// An edge, given by the positions of the two end points, is shared by more than two triangles.
+    NonManifoldEdge {edge: [Vec3; 2], triangle_count: usize}
+}
+
+impl MeshIssue {
+    ///
+    /// Returns true if the issue prevents the mesh from being rendered correctly,
+    /// while degenerate triangles and non-manifold edges are only reported as warnings.
+    ///
+    pub fn is_error(&self) -> bool {
+        !matches!(self, MeshIssue::DegenerateTriangle {..} | MeshIssue::NonManifoldEdge {..})
+    }
+}
+
+impl std::fmt::Display for MeshIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MeshIssue::PositionCount {count} => write!(f, "the number of position values ({}) is not a multiple of 3", count),
+            MeshIssue::IndexCount {count, primitive_type} => write!(f, "the number of indices ({}) is not a multiple of {}", count, primitive_type.vertex_count()),
+            MeshIssue::IndexOutOfRange {primitive, index} => write!(f, "primitive {} refers to the vertex {} which does not exist", primitive, index),
+            MeshIssue::AttributeLength {attribute, expected, actual} => write!(f, "expected {} {} values but got {}", expected, attribute, actual),
+            MeshIssue::NotFinite {attribute, vertex} => write!(f, "the {} of vertex {} is not finite", attribute, vertex),
+            MeshIssue::DegenerateTriangle {triangle} => write!(f, "triangle {} is degenerate", triangle),
+            MeshIssue::NonManifoldEdge {edge, triangle_count} => write!(f, "the edge from ({}, {}, {}) to ({}, {}, {}) is shared by {} triangles",
+                edge[0].x, edge[0].y, edge[0].z, edge[1].x, edge[1].y, edge[1].z, triangle_count)
+        }
+    }
+}
+
+///
+/// The result of [validating](crate::CPUMesh::validate) a mesh.
+///
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde-support", derive(serde::Serialize, serde::Deserialize))]
+pub struct MeshValidationReport {
+    /// The name of the validated mesh.
+    pub mesh_name: String,
+    /// All of the issues found in the mesh.
+    pub issues: Vec<MeshIssue>
+}
+
+impl MeshValidationReport {
+    ///
+    /// Returns true if none of the issues are [errors](crate::MeshIssue::is_error), ie. the mesh can be rendered.
+    ///
+    pub fn is_valid(&self) -> bool {
+        !self.issues.iter().any(|issue| issue.is_error())
+    }
+}
+
+impl std::fmt::Display for MeshValidationReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        const MAX_LISTED: usize = 10;
+        write!(f, "the mesh {} has {} issues", self.mesh_name, self.issues.len())?;
+        for issue in self.issues.iter().take(MAX_LISTED) {
+            write!(f, "\n  {}: {}", if issue.is_error() { "error" } else { "warning" }, issue)?;
+        }
+        if self.issues.len() > MAX_LISTED {
+            write!(f, "\n  ... and {} more", self.issues.len() - MAX_LISTED)?;
+        }
+        Ok(())
+    }
+}
+
+impl CPUMesh {
+    ///
+    /// Checks the mesh for out of range indices, attributes (normals, uvs, colors and tangents) with a length which does not match the positions,
+    /// values which are NaN or infinite, degenerate triangles and non-manifold edges.
+    /// Degenerate triangles and non-manifold edges are only checked for triangle meshes.
+    /// Use [repair](Self::repair) to fix the issues which can be fixed.
+    ///
+    pub fn validate(&self) -> MeshValidationReport {
+        let mut issues = Vec::new();
+        if self.positions.len() % 3 != 0 {
+            issues.push(MeshIssue::PositionCount {count: self.positions.len()});
+        }
+        let size = self.primitive_type.vertex_count();
+        if let Some(ref indices) = self.indices {
+            if indices.len() % size != 0 {
+                issues.push(MeshIssue::IndexCount {count: indices.len(), primitive_type: self.primitive_type});
+            }
+        }
+        let vertex_count = self.positions.len() / 3;
+        for (attribute, size, length) in self.attribute_lengths() {
+            if length != vertex_count * size {
+                issues.push(MeshIssue::AttributeLength {attribute: attribute.to_string(), expected: vertex_count * size, actual: length});
+            }
+        }
+        for (attribute, size, values) in self.float_attributes() {
+            for (vertex, value) in values.chunks(size).enumerate() {
+                if value.iter().any(|v| !v.is_finite()) {
+                    issues.push(MeshIssue::NotFinite {attribute: attribute.to_string(), vertex});
+                }
+            }
+        }
+
+        let is_triangle_mesh = self.primitive_type == PrimitiveType::Triangles;
+        let mut edges: HashMap<[u32; 2], usize> = HashMap::new();
+        let welded = if is_triangle_mesh { self.welded_vertices() } else { Vec::new() };
+        for (primitive, face) in self.primitive_indices().chunks_exact(size).enumerate() {
+            if let Some(index) = face.iter().find(|i| **i as usize >= vertex_count) {
+                issues.push(MeshIssue::IndexOutOfRange {primitive, index: *index});
+            } else if !is_triangle_mesh {
+                continue;
+            } else if self.is_degenerate(face) {
+                issues.push(MeshIssue::DegenerateTriangle {triangle: primitive});
+            } else {
+                for i in 0..3 {
+                    let (a, b) = (welded[face[i] as usize], welded[face[(i + 1) % 3] as usize]);
+                    *edges.entry([a.min(b), a.max(b)]).or_insert(0) += 1;
+                }
+            }
+        }
+        let mut non_manifold_edges: Vec<_> = edges.into_iter().filter(|(_, count)| *count > 2).collect();
+        non_manifold_edges.sort();
+        for ([a, b], triangle_count) in non_manifold_edges {
+            issues.push(MeshIssue::NonManifoldEdge {edge: [self.position(a), self.position(b)], triangle_count});
+        }
+        MeshValidationReport {mesh_name: self.name.clone(), issues}
+    }
+
+    ///
+    /// Fixes the issues found by [validate](Self::validate) which can be fixed and returns the report of the remaining issues.
+    /// Attributes with a wrong length are removed (the normals are recomputed), uv coordinates which are not finite are set to zero,
+    /// primitives with out of range indices or positions which are not finite and triangles with no area are removed,
+    /// and for indexed meshes, the vertices which are no longer used are removed.
+    /// Non-manifold edges cannot be fixed.
+    ///
+    pub fn repair(&mut self) -> MeshValidationReport {
+        self.positions.truncate(self.positions.len() / 3 * 3);
+        let vertex_count = self.positions.len() / 3;
+        let mut recompute_normals = false;
+        if self.normals.as_ref().map(|n| n.len() != vertex_count * 3 || n.iter().any(|v| !v.is_finite())).unwrap_or(false) {
+            self.normals = None;
+            recompute_normals = true;
+        }
+        if self.uvs.as_ref().map(|uvs| uvs.len() != vertex_count * 2).unwrap_or(false) {
+            self.uvs = None;
+        }
+        if let Some(ref mut uvs) = self.uvs {
+            uvs.iter_mut().filter(|v| !v.is_finite()).for_each(|v| *v = 0.0);
+        }
+        if self.colors.as_ref().map(|colors| colors.len() != vertex_count * 4).unwrap_or(false) {
+            self.colors = None;
+        }
+        if self.tangents.as_ref().map(|t| t.len() != vertex_count * 4 || t.iter().any(|v| !v.is_finite())).unwrap_or(false) {
+            self.tangents = None;
+        }
+
+        let size = self.primitive_type.vertex_count();
+        let keep: Vec<bool> = self.primitive_indices().chunks_exact(size).map(|face| {
+            face.iter().all(|i| (*i as usize) < vertex_count && self.positions[*i as usize * 3..*i as usize * 3 + 3].iter().all(|v| v.is_finite()))
+                && (self.primitive_type != PrimitiveType::Triangles || !self.is_degenerate(face))
+        }).collect();
+        if let Some(ref indices) = self.indices {
+            let primitives: Vec<u32> = indices.chunks(size).zip(keep.iter()).filter(|(_, keep)| **keep).flat_map(|(face, _)| face.to_vec()).collect();
+            // Remove the vertices which are no longer used
+            let mut used = vec![false; vertex_count];
+            primitives.iter().for_each(|i| used[*i as usize] = true);
+            let mut new_index = vec![0u32; vertex_count];
+            let mut count = 0;
+            for vertex in 0..vertex_count {
+                if used[vertex] {
+                    new_index[vertex] = count;
+                    count += 1;
+                }
+            }
+            self.indices = Some(primitives.iter().map(|i| new_index[*i as usize]).collect());
+            self.retain_vertices(|vertex| used[vertex]);
+        } else {
+            self.retain_vertices(|vertex| keep.get(vertex / size).copied().unwrap_or(false));
+        }
+
+        if recompute_normals {
+            self.compute_normals();
+        }
+        self.validate()
+    }
+
+    // A cheap check for the issues which are errors, used before creating a GPU mesh so the full report is only built if the check fails
+    pub(crate) fn is_valid(&self) -> bool {
+        let vertex_count = self.positions.len() / 3;
+        self.positions.len() % 3 == 0
+            && self.indices.as_ref().map(|indices| indices.len() % self.primitive_type.vertex_count() == 0 && indices.iter().all(|i| (*i as usize) < vertex_count)).unwrap_or(true)
+            && self.attribute_lengths().iter().all(|(_, size, length)| *length == vertex_count * size)
+            && self.float_attributes().iter().all(|(_, _, values)| values.iter().all(|v| v.is_finite()))
+    }
+
+    fn attribute_lengths(&self) -> Vec<(&'static str, usize, usize)> {
+        let mut lengths = Vec::new();
+        if let Some(ref normals) = self.normals { lengths.push(("normal", 3, normals.len())); }
+        if let Some(ref uvs) = self.uvs { lengths.push(("uv", 2, uvs.len())); }
+        if let Some(ref colors) = self.colors { lengths.push(("color", 4, colors.len())); }
+        if let Some(ref tangents) = self.tangents { lengths.push(("tangent", 4, tangents.len())); }
+        lengths
+    }
+
+    fn float_attributes(&self) -> Vec<(&'static str, usize, &[f32])> {
+        let mut attributes = vec![("position", 3, &self.positions[..])];
+        if let Some(ref normals) = self.normals { attributes.push(("normal", 3, normals)); }
+        if let Some(ref uvs) = self.uvs { attributes.push(("uv", 2, uvs)); }
+        if let Some(ref tangents) = self.tangents { attributes.push(("tangent", 4, tangents)); }
+        attributes
+    }
+
+    // The vertex indices of the primitives, also for meshes without indices, excluding an incomplete primitive at the end
+    fn primitive_indices(&self) -> Vec<u32> {
+        let size = self.primitive_type.vertex_count();
+        match self.indices {
+            Some(ref indices) => indices[..indices.len() / size * size].to_vec(),
+            None => (0..(self.positions.len() / 3 / size * size) as u32).collect()
+        }
+    }
+
+    fn position(&self, vertex: u32) -> Vec3 {
+        let i = vertex as usize * 3;
+        vec3(self.positions[i], self.positions[i + 1], self.positions[i + 2])
+    }
+
+    fn is_degenerate(&self, face: &[u32]) -> bool {
+        if face[0] == face[1] || face[1] == face[2] || face[2] == face[0] {
+            return true;
+        }
+        let (p0, p1, p2) = (self.position(face[0]), self.position(face[1]), self.position(face[2]));
+        let longest_edge = (p1 - p0).magnitude2().max((p2 - p1).magnitude2()).max((p0 - p2).magnitude2());
+        (p1 - p0).cross(p2 - p0).magnitude() <= 1e-7 * longest_edge
+    }
+
+    // Maps each vertex to the first vertex with the same position, so edges across uv or normal seams are identified
+    fn welded_vertices(&self) -> Vec<u32> {
+        let mut first: HashMap<[u32; 3], u32> = HashMap::new();
+        self.positions.chunks_exact(3).enumerate().map(|(vertex, p)| {
+            *first.entry([p[0].to_bits(), p[1].to_bits(), p[2].to_bits()]).or_insert(vertex as u32)
+        }).collect()
+    }
+
+    fn retain_vertices<F: Fn(usize) -> bool>(&mut self, keep: F) {
+        fn retain<T: Copy>(values: &mut Vec<T>, size: usize, keep: &dyn Fn(usize) -> bool) {
+            *values = values.chunks(size).enumerate().filter(|(vertex, _)| keep(*vertex)).flat_map(|(_, value)| value.to_vec()).collect();
+        }
+        retain(&mut self.positions, 3, &keep);
+        if let Some(ref mut normals) = self.normals { retain(normals, 3, &keep); }
+        if let Some(ref mut uvs) = self.uvs { retain(uvs, 2, &keep); }
+        if let Some(ref mut colors) = self.colors { retain(colors, 4, &keep); }
+        if let Some(ref mut tangents) = self.tangents { retain(tangents, 4, &keep); }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quad() -> CPUMesh {
+        CPUMesh {
+            name: "quad".to_string(),
+            positions: vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 1.0, 0.0],
+            indices: Some(vec![0, 1, 2, 0, 2, 3]),
+            normals: Some([0.0, 0.0, 1.0].repeat(4)),
+            uvs: Some(vec![0.0, 0.0, 1.0, 0.0, 1.0, 1.0, 0.0, 1.0]),
+            ..Default::default()
+        }
+    }
+
+    fn invalid_quad() -> CPUMesh {
+        let mut cpu_mesh = quad();
+        cpu_mesh.indices = Some(vec![0, 1, 2, 0, 2, 4]);
+        cpu_mesh.normals.as_mut().unwrap()[4] = f32::NAN;
+        cpu_mesh.uvs.as_mut().unwrap().truncate(6);
+        cpu_mesh
+    }
+
+    #[test]
+    fn validate_valid() {
+        let cpu_mesh = quad();
+        assert_eq!(cpu_mesh.validate(), MeshValidationReport {mesh_name: "quad".to_string(), issues: vec![]});
+        assert!(cpu_mesh.is_valid());
+    }
+
+    #[test]
+    fn validate_errors() {
+        let cpu_mesh = invalid_quad();
+        let report = cpu_mesh.validate();
+        assert_eq!(report.issues, vec![
+            MeshIssue::AttributeLength {attribute: "uv".to_string(), expected: 8, actual: 6},
+            MeshIssue::NotFinite {attribute: "normal".to_string(), vertex: 1},
+            MeshIssue::IndexOutOfRange {primitive: 1, index: 4}
+        ]);
+        assert!(!report.is_valid());
+        assert!(!cpu_mesh.is_valid());
+        assert_eq!(report.to_string(), "the mesh quad has 3 issues\n  error: expected 8 uv values but got 6\n  error: the normal of vertex 1 is not finite\n  error: primitive 1 refers to the vertex 4 which does not exist");
+    }
+
+    #[test]
+    fn validate_warnings() {
+        // Four triangles sharing the edge from vertex 0 to vertex 1, where the last one has no area
+        let cpu_mesh = CPUMesh {
+            positions: vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, -1.0, 0.0, 0.0, 0.0, 1.0, 2.0, 0.0, 0.0],
+            indices: Some(vec![0, 1, 2, 1, 0, 3, 0, 1, 4, 0, 1, 5]),
+            ..Default::default()
+        };
+        let report = cpu_mesh.validate();
+        assert_eq!(report.issues, vec![
+            MeshIssue::DegenerateTriangle {triangle: 3},
+            MeshIssue::NonManifoldEdge {edge: [vec3(0.0, 0.0, 0.0), vec3(1.0, 0.0, 0.0)], triangle_count: 3}
+        ]);
+        assert!(report.is_valid());
+        assert!(cpu_mesh.is_valid());
+    }
+
+    #[test]
+    fn validate_lines_and_points() {
+        let lines = CPUMesh {primitive_type: PrimitiveType::Lines, indices: Some(vec![0, 1, 1, 2, 2, 3, 3, 0]), normals: None, uvs: None, ..quad()};
+        assert!(lines.validate().issues.is_empty());
+        assert!(lines.is_valid());
+
+        let lines = CPUMesh {primitive_type: PrimitiveType::Lines, indices: Some(vec![0, 1, 1]), normals: None, uvs: None, ..quad()};
+        assert_eq!(lines.validate().issues, vec![MeshIssue::IndexCount {count: 3, primitive_type: PrimitiveType::Lines}]);
+        assert!(!lines.is_valid());
+
+        // Points are never degenerate
+        let points = CPUMesh {primitive_type: PrimitiveType::Points, indices: Some(vec![0, 0, 4]), normals: None, uvs: None, ..quad()};
+        assert_eq!(points.validate().issues, vec![MeshIssue::IndexOutOfRange {primitive: 2, index: 4}]);
+        assert!(!points.is_valid());
+    }
+
+    #[test]
+    fn repair_lines() {
+        let mut lines = CPUMesh {primitive_type: PrimitiveType::Lines, indices: Some(vec![0, 1, 1, 5, 2, 3]), normals: None, uvs: None, ..quad()};
+        assert!(lines.repair().issues.is_empty());
+        assert_eq!(lines.indices, Some(vec![0, 1, 2, 3]));
+        assert_eq!(lines.positions.len(), 12);
+    }
+
+    #[test]
+    fn repair_indexed() {
+        let mut cpu_mesh = invalid_quad();
+        let report = cpu_mesh.repair();
+        assert!(report.issues.is_empty());
+        // The triangle with the out of range index and the vertex which is no longer used are removed,
+        // the uvs with the wrong length are removed and the normals are recomputed
+        assert_eq!(cpu_mesh.positions, vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0, 0.0]);
+        assert_eq!(cpu_mesh.indices, Some(vec![0, 1, 2]));
+        assert_eq!(cpu_mesh.normals, Some([0.0, 0.0, 1.0].repeat(3)));
+        assert_eq!(cpu_mesh.uvs, None);
+    }
+
+    #[test]
+    fn repair_without_indices() {
+        let mut positions = vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 0.0, 0.0, f32::INFINITY, 1.0, 0.0, 0.0, 1.0, 0.0, 5.0];
+        let mut cpu_mesh = CPUMesh {positions: positions.clone(), uvs: Some(vec![0.0; 12]), ..Default::default()};
+        cpu_mesh.uvs.as_mut().unwrap()[1] = f32::NAN;
+        assert!(!cpu_mesh.is_valid());
+        let report = cpu_mesh.repair();
+        assert!(report.issues.is_empty());
+        // The incomplete position and the triangle with an infinite position are removed and the uv which is not a number is set to zero
+        positions.truncate(9);
+        assert_eq!(cpu_mesh.positions, positions);
+        assert_eq!(cpu_mesh.uvs, Some(vec![0.0; 6]));
+    }
+}
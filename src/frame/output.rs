@@ -24,7 +24,90 @@ pub struct FrameOutput {
     /// Takes a screenshot if this is set to some path and saves it at the given location.
     /// Only works on desktop, will be ignored on web.
     ///
-    pub screenshot: Option<std::path::PathBuf>
+    pub screenshot: Option<std::path::PathBuf>,
+
+    ///
+    /// The content and format of the [screenshot](Self::screenshot).
+    ///
+    pub screenshot_format: ScreenshotFormat,
+
+    ///
+    /// Records the following frames as long as this is set to some [recording](crate::Recording).
+    /// The [elapsed time](crate::FrameInput::elapsed_time) of the recorded frames is the fixed
+    /// [time step](crate::Recording::time_step) of the recording instead of the real time.
+    /// If the recording fails, the error is logged and the recording is stopped.
+    /// The recording is finished when this is set to none or the window is closed.
+    /// Only works on desktop, will be ignored on web.
+    ///
+    pub recording: Option<Recording>
+}
+
+///
+/// Settings for recording the rendered frames to a numbered image sequence and/or a video encoder,
+/// see [FrameOutput::recording](crate::FrameOutput::recording).
+///
+#[derive(Clone, Debug, PartialEq)]
+pub struct Recording {
+    ///
+    /// Saves the frames as a numbered image sequence if this is set to some path.
+    /// A sequence of `#` in the file name is replaced by the zero padded frame number, for example `frames/turntable_####.png`,
+    /// otherwise the frame number is appended to the file name.
+    ///
+    pub image_path: Option<std::path::PathBuf>,
+
+    ///
+    /// Starts the given command (the program followed by the arguments) and writes the frames to its standard input if this is set to some command.
+    /// The frames are written as raw 8-bit RGB pixels starting with the top row.
+    /// The texts `{width}`, `{height}` and `{fps}` in the arguments are replaced by the frame size in pixels and the frame rate, for example
+    /// `ffmpeg -y -f rawvideo -pix_fmt rgb24 -s {width}x{height} -r {fps} -i - turntable.mp4`.
+    ///
+    pub encoder_command: Option<Vec<String>>,
+
+    ///
+    /// Only every Nth frame is recorded, for example 2 to record every second frame.
+    ///
+    pub frame_step: usize,
+
+    ///
+    /// The fixed simulated time in milliseconds between the frames while recording.
+    ///
+    pub time_step: f64
+}
+
+impl Recording {
+    ///
+    /// The frame rate of the recording, ie. the number of recorded frames per simulated second.
+    ///
+    pub fn frames_per_second(&self) -> f64 {
+        1000.0 / (self.time_step * self.frame_step.max(1) as f64)
+    }
+}
+
+impl Default for Recording {
+    fn default() -> Self {
+        Self {
+            image_path: None,
+            encoder_command: None,
+            frame_step: 1,
+            time_step: 1000.0 / 60.0
+        }
+    }
+}
+
+///
+/// The content and format of a screenshot, see [FrameOutput::screenshot](crate::FrameOutput::screenshot).
+/// The file type is chosen by the extension of the screenshot path.
+///
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ScreenshotFormat {
+    /// The RGB color of the screen.
+    RGB,
+    /// The RGBA color of the screen, for example for a transparent background. Requires a file type which supports transparency, for example .png.
+    RGBA,
+    /// The depth of the screen, normalized to a grayscale image.
+    Depth,
+    /// The raw floating point depth of the screen. Requires the .exr or .hdr file type.
+    DepthF32
 }
 
 impl Default for FrameOutput {
@@ -32,7 +115,9 @@ impl Default for FrameOutput {
         Self {
             exit: false,
             swap_buffers: true,
-            screenshot: None
+            screenshot: None,
+            screenshot_format: ScreenshotFormat::RGB,
+            recording: None
         }
     }
 }
\ No newline at end of file
@@ -37,6 +37,37 @@ pub mod obj;
 #[cfg(feature = "obj-io")]
 pub use obj::*;
 
+#[doc(hidden)]
+#[cfg(feature = "gltf-io")]
+pub mod gltf;
+#[doc(inline)]
+#[cfg(feature = "gltf-io")]
+pub use self::gltf::*;
+
+#[doc(hidden)]
+#[cfg(feature = "stl-io")]
+pub mod stl;
+#[doc(inline)]
+#[cfg(feature = "stl-io")]
+pub use stl::*;
+
+#[doc(hidden)]
+#[cfg(feature = "ply-io")]
+pub mod ply;
+#[doc(inline)]
+#[cfg(feature = "ply-io")]
+pub use ply::*;
+
+#[doc(hidden)]
+#[cfg(feature = "zip-io")]
+pub mod zip;
+#[doc(inline)]
+#[cfg(feature = "zip-io")]
+pub use self::zip::*;
+
+#[cfg(all(feature = "http-io", not(target_arch = "wasm32")))]
+mod http;
+
 ///
 /// Error message from the [core](crate::io) module.
 ///
@@ -48,12 +79,64 @@ pub enum IOError {
     Bincode(bincode::Error),
     #[cfg(feature = "obj-io")]
     Obj(wavefront_obj::ParseError),
+    #[cfg(feature = "gltf-io")]
+    Gltf(::gltf::Error),
     #[cfg(not(target_arch = "wasm32"))]
     IO(std::io::Error),
+    #[cfg(not(target_arch = "wasm32"))]
+    FailedToReadFile {path: std::path::PathBuf, source: std::io::Error},
+    #[cfg(not(target_arch = "wasm32"))]
+    FailedToWriteFile {path: std::path::PathBuf, source: std::io::Error},
     FailedToLoad {message: String},
+    FailedToFetch {url: String, status: Option<u16>, message: String},
     FailedToSave {message: String}
 }
 
+impl std::fmt::Display for IOError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            #[cfg(feature = "image-io")]
+            IOError::Image(e) => write!(f, "image error: {}", e),
+            #[cfg(feature = "3d-io")]
+            IOError::Bincode(e) => write!(f, "could not (de)serialize .3d data: {}", e),
+            #[cfg(feature = "obj-io")]
+            IOError::Obj(e) => write!(f, "could not parse .obj file: {}", e),
+            #[cfg(feature = "gltf-io")]
+            IOError::Gltf(e) => write!(f, "could not parse glTF file: {}", e),
+            #[cfg(not(target_arch = "wasm32"))]
+            IOError::IO(e) => write!(f, "io error: {}", e),
+            #[cfg(not(target_arch = "wasm32"))]
+            IOError::FailedToReadFile {path, source} => write!(f, "failed to read file {}: {}", path.display(), source),
+            #[cfg(not(target_arch = "wasm32"))]
+            IOError::FailedToWriteFile {path, source} => write!(f, "failed to write file {}: {}", path.display(), source),
+            IOError::FailedToLoad {message} => write!(f, "failed to load: {}", message),
+            IOError::FailedToFetch {url, status: Some(status), message} => write!(f, "failed to fetch {} (HTTP status {}): {}", url, status, message),
+            IOError::FailedToFetch {url, status: None, message} => write!(f, "failed to fetch {}: {}", url, message),
+            IOError::FailedToSave {message} => write!(f, "failed to save: {}", message)
+        }
+    }
+}
+
+impl std::error::Error for IOError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "image-io")]
+            IOError::Image(e) => Some(e),
+            #[cfg(feature = "3d-io")]
+            IOError::Bincode(e) => Some(e.as_ref()),
+            #[cfg(feature = "obj-io")]
+            IOError::Obj(e) => Some(e),
+            #[cfg(feature = "gltf-io")]
+            IOError::Gltf(e) => Some(e),
+            #[cfg(not(target_arch = "wasm32"))]
+            IOError::IO(e) => Some(e),
+            #[cfg(not(target_arch = "wasm32"))]
+            IOError::FailedToReadFile {source, ..} | IOError::FailedToWriteFile {source, ..} => Some(source),
+            _ => None
+        }
+    }
+}
+
 #[cfg(feature = "image-io")]
 impl From<image::ImageError> for IOError {
     fn from(other: image::ImageError) -> Self {
@@ -75,6 +158,13 @@ impl From<wavefront_obj::ParseError> for IOError {
     }
 }
 
+#[cfg(feature = "gltf-io")]
+impl From<::gltf::Error> for IOError {
+    fn from(other: ::gltf::Error) -> Self {
+        IOError::Gltf(other)
+    }
+}
+
 #[cfg(not(target_arch = "wasm32"))]
 impl From<std::io::Error> for IOError {
     fn from(other: std::io::Error) -> Self {
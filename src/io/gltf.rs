@@ -0,0 +1,771 @@
+use crate::io::*;
+use std::path::Path;
+use std::rc::Rc;
+use std::collections::HashMap;
+use crate::definition::*;
+use crate::math::*;
+
+impl Loaded {
+    ///
+    /// Deserialize a loaded .gltf file (with its .bin and image resources) or .glb file resource into a list of meshes and materials.
+    /// The node hierarchy of the default scene (or the first scene if no default is specified) is flattened, ie. the node transformations are applied to the positions and normals.
+    /// Any resources which are not embedded (buffers and images referenced by a relative path) must also be loaded.
+    /// Primitives consisting of points or lines (also line strips and loops) are loaded as meshes with the corresponding [primitive type](crate::PrimitiveType).
+    /// It uses the [gltf](https://crates.io/crates/gltf/main.rs) crate.
+    ///
+    /// # Feature
+    /// Only available when the `gltf-io` feature is enabled.
+    ///
+    pub fn gltf<P: AsRef<Path>>(&self, path: P) -> Result<(Vec<CPUMesh>, Vec<CPUMaterial>), IOError> {
+        let ::gltf::Gltf { document, blob } = ::gltf::Gltf::from_slice(self.bytes(path.as_ref())?)?;
+        let base_path = path.as_ref().parent().unwrap_or(Path::new("./"));
+
+        let mut buffers = Vec::new();
+        for buffer in document.buffers() {
+            let mut data = match buffer.source() {
+                ::gltf::buffer::Source::Bin => blob.as_ref().ok_or(
+                    IOError::FailedToLoad {message: "The glTF file references a binary chunk, but it does not have any.".to_string()})?.clone(),
+                ::gltf::buffer::Source::Uri(uri) => self.gltf_uri_bytes(base_path, uri)?
+            };
+            if data.len() < buffer.length() {
+                Err(IOError::FailedToLoad {message: format!("The glTF buffer {} contains {} bytes, but expected {} bytes.", buffer.index(), data.len(), buffer.length())})?;
+            }
+            while data.len() % 4 != 0 {
+                data.push(0);
+            }
+            buffers.push(data);
+        }
+        // The buffer views are sliced without bounds checks when reading accessors, so make sure they are within the buffers
+        for view in document.views() {
+            if view.offset() + view.length() > buffers[view.buffer().index()].len() {
+                Err(IOError::FailedToLoad {message: format!("The glTF buffer view {} is outside of the buffer {}.", view.index(), view.buffer().index())})?;
+            }
+        }
+
+        // Textures referenced by several materials are shared
+        let mut textures = HashMap::new();
+        let mut cpu_materials = Vec::new();
+        for material in document.materials() {
+            let pbr = material.pbr_metallic_roughness();
+            let color = pbr.base_color_factor();
+            let roughness = pbr.roughness_factor().max(0.01);
+            cpu_materials.push(CPUMaterial {
+                name: gltf_material_name(&material),
+                color: Some((color[0], color[1], color[2], color[3])),
+                texture_image: if let Some(info) = pbr.base_color_texture() {
+                    Some(self.gltf_texture(base_path, &buffers, &info.texture(), &mut textures)?)
+                } else {None},
+                diffuse_intensity: None,
+                specular_intensity: None,
+                specular_power: Some(2.0 / (roughness * roughness) - 2.0),
+                emissive_color: Some(material.emissive_factor()).filter(|e| e.iter().any(|c| *c > 0.0)).map(|e| (e[0], e[1], e[2])),
+                bump_image: if let Some(info) = material.normal_texture() {
+                    Some(self.gltf_texture(base_path, &buffers, &info.texture(), &mut textures)?)
+                } else {None},
+                ..Default::default()
+            });
+        }
+
+        let mut cpu_meshes = Vec::new();
+        if let Some(scene) = document.default_scene().or_else(|| document.scenes().next()) {
+            for node in scene.nodes() {
+                parse_tree(&node, &Mat4::identity(), &buffers, &mut cpu_meshes)?;
+            }
+        } else {
+            for mesh in document.meshes() {
+                parse_mesh(&mesh, mesh.name().unwrap_or("mesh"), &Mat4::identity(), &buffers, &mut cpu_meshes)?;
+            }
+        }
+        Ok((cpu_meshes, cpu_materials))
+    }
+
+    fn gltf_uri_bytes(&self, base_path: &Path, uri: &str) -> Result<Vec<u8>, IOError> {
+        if uri.starts_with("data:") {
+            let (_, data) = uri.split_once(',').ok_or(
+                IOError::FailedToLoad {message: "The glTF file contains an invalid data uri.".to_string()})?;
+            base64::decode(data).map_err(|e| IOError::FailedToLoad {message: format!("Could not decode glTF data uri: {}", e)})
+        } else {
+            Ok(self.bytes(base_path.join(percent_decode(uri)))?.to_vec())
+        }
+    }
+
+    fn gltf_texture(&self, base_path: &Path, buffers: &[Vec<u8>], texture: &::gltf::Texture,
+                    textures: &mut HashMap<usize, Rc<CPUTexture<u8>>>) -> Result<Rc<CPUTexture<u8>>, IOError> {
+        if let Some(cpu_texture) = textures.get(&texture.index()) {
+            return Ok(cpu_texture.clone());
+        }
+        let mut cpu_texture = match texture.source().source() {
+            ::gltf::image::Source::View { view, .. } => {
+                let bytes = buffers.get(view.buffer().index()).and_then(|buffer| buffer.get(view.offset()..view.offset() + view.length()))
+                    .ok_or_else(|| IOError::FailedToLoad {message: format!("The glTF image {} refers to bytes outside of the buffer.", texture.source().index())})?;
+                image_from_bytes(bytes)?
+            },
+            ::gltf::image::Source::Uri { uri, .. } => image_from_bytes(&self.gltf_uri_bytes(base_path, uri)?)?
+        };
+
+        let sampler = texture.sampler();
+        if let Some(filter) = sampler.mag_filter() {
+            cpu_texture.mag_filter = match filter {
+                ::gltf::texture::MagFilter::Nearest => Interpolation::Nearest,
+                ::gltf::texture::MagFilter::Linear => Interpolation::Linear
+            };
+        }
+        if let Some(filter) = sampler.min_filter() {
+            use ::gltf::texture::MinFilter;
+            let (min_filter, mip_map_filter) = match filter {
+                MinFilter::Nearest => (Interpolation::Nearest, None),
+                MinFilter::Linear => (Interpolation::Linear, None),
+                MinFilter::NearestMipmapNearest => (Interpolation::Nearest, Some(Interpolation::Nearest)),
+                MinFilter::LinearMipmapNearest => (Interpolation::Linear, Some(Interpolation::Nearest)),
+                MinFilter::NearestMipmapLinear => (Interpolation::Nearest, Some(Interpolation::Linear)),
+                MinFilter::LinearMipmapLinear => (Interpolation::Linear, Some(Interpolation::Linear))
+            };
+            cpu_texture.min_filter = min_filter;
+            cpu_texture.mip_map_filter = mip_map_filter;
+        }
+        cpu_texture.wrap_s = wrapping_from(sampler.wrap_s());
+        cpu_texture.wrap_t = wrapping_from(sampler.wrap_t());
+        let cpu_texture = Rc::new(cpu_texture);
+        textures.insert(texture.index(), cpu_texture.clone());
+        Ok(cpu_texture)
+    }
+}
+
+fn parse_tree(node: &::gltf::Node, parent_transform: &Mat4, buffers: &[Vec<u8>], cpu_meshes: &mut Vec<CPUMesh>) -> Result<(), IOError> {
+    let transform = parent_transform * Mat4::from(node.transform().matrix());
+    if let Some(mesh) = node.mesh() {
+        let name = mesh.name().or(node.name()).map(|n| n.to_string()).unwrap_or(format!("mesh{}", mesh.index()));
+        parse_mesh(&mesh, &name, &transform, buffers, cpu_meshes)?;
+    }
+    for child in node.children() {
+        parse_tree(&child, &transform, buffers, cpu_meshes)?;
+    }
+    Ok(())
+}
+
+fn parse_mesh(mesh: &::gltf::Mesh, name: &str, transform: &Mat4, buffers: &[Vec<u8>], cpu_meshes: &mut Vec<CPUMesh>) -> Result<(), IOError> {
+    use ::gltf::mesh::Mode;
+    let normal_transform = transform.invert().unwrap_or(Mat4::identity()).transpose();
+    for primitive in mesh.primitives() {
+        let mode = primitive.mode();
+        let primitive_type = match mode {
+            Mode::Points => PrimitiveType::Points,
+            Mode::Lines | Mode::LineStrip | Mode::LineLoop => PrimitiveType::Lines,
+            Mode::Triangles | Mode::TriangleStrip | Mode::TriangleFan => PrimitiveType::Triangles
+        };
+        let reader = primitive.reader(|buffer| buffers.get(buffer.index()).map(|data| &data[..]));
+
+        let mut positions = Vec::new();
+        for p in reader.read_positions().ok_or(
+            IOError::FailedToLoad {message: format!("The glTF mesh {} has a primitive without positions.", name)})? {
+            let p = transform * vec4(p[0], p[1], p[2], 1.0);
+            positions.push(p.x);
+            positions.push(p.y);
+            positions.push(p.z);
+        }
+
+        let normals = reader.read_normals().map(|values| {
+            let mut normals = Vec::new();
+            for n in values {
+                let n = (normal_transform * vec4(n[0], n[1], n[2], 0.0)).truncate().normalize();
+                normals.push(n.x);
+                normals.push(n.y);
+                normals.push(n.z);
+            }
+            normals
+        });
+
+        let tangents = reader.read_tangents().map(|values| {
+            // The handedness is flipped since the v coordinate is flipped and also flipped by a mirroring transformation
+            let handedness = if transform.determinant() < 0.0 { 1.0 } else { -1.0 };
+            let mut tangents = Vec::new();
+            for t in values {
+                let tangent = (transform * vec4(t[0], t[1], t[2], 0.0)).truncate().normalize();
+                tangents.extend_from_slice(&[tangent.x, tangent.y, tangent.z, handedness * t[3]]);
+            }
+            tangents
+        });
+
+        let uvs = reader.read_tex_coords(0).map(|values| {
+            let mut uvs = Vec::new();
+            for uv in values.into_f32() {
+                uvs.push(uv[0]);
+                uvs.push(1.0 - uv[1]);
+            }
+            uvs
+        });
+
+        let colors = reader.read_colors(0).map(|values| values.into_rgba_u8().flat_map(|c| c.to_vec()).collect());
+
+        let mut indices = reader.read_indices().map(|values| values.into_u32().collect::<Vec<_>>());
+        if mode == Mode::LineStrip || mode == Mode::LineLoop {
+            let vertex_indices = indices.unwrap_or((0..positions.len() as u32 / 3).collect());
+            let mut lines: Vec<u32> = vertex_indices.windows(2).flatten().cloned().collect();
+            if mode == Mode::LineLoop && vertex_indices.len() > 2 {
+                lines.extend_from_slice(&[vertex_indices[vertex_indices.len() - 1], vertex_indices[0]]);
+            }
+            indices = Some(lines);
+        } else if primitive_type == PrimitiveType::Triangles && (mode != Mode::Triangles || transform.determinant() < 0.0) {
+            let vertex_indices = indices.unwrap_or((0..positions.len() as u32 / 3).collect());
+            let mut triangles = match mode {
+                Mode::TriangleStrip => (0..vertex_indices.len().max(2) - 2).map(|i|
+                    if i % 2 == 0 { [vertex_indices[i], vertex_indices[i+1], vertex_indices[i+2]] }
+                    else { [vertex_indices[i+1], vertex_indices[i], vertex_indices[i+2]] }).collect::<Vec<_>>(),
+                Mode::TriangleFan => (1..vertex_indices.len().max(1) - 1).map(|i|
+                    [vertex_indices[0], vertex_indices[i], vertex_indices[i+1]]).collect::<Vec<_>>(),
+                _ => vertex_indices.chunks_exact(3).map(|t| [t[0], t[1], t[2]]).collect::<Vec<_>>()
+            };
+            if transform.determinant() < 0.0 {
+                // A mirroring transformation flips the winding order
+                for triangle in triangles.iter_mut() {
+                    triangle.swap(1, 2);
+                }
+            }
+            indices = Some(triangles.iter().flatten().cloned().collect());
+        }
+
+        cpu_meshes.push(CPUMesh {
+            name: name.to_string(),
+            material_name: primitive.material().index().map(|_| gltf_material_name(&primitive.material())),
+            primitive_type,
+            positions,
+            indices,
+            normals,
+            tangents,
+            uvs,
+            colors
+        });
+    }
+    Ok(())
+}
+
+fn gltf_material_name(material: &::gltf::Material) -> String {
+    material.name().map(|n| n.to_string()).unwrap_or(format!("material{}", material.index().unwrap_or(0)))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn sampler_json(cpu_texture: &CPUTexture<u8>) -> serde_json::Value {
+    let filter = |interpolation: Interpolation| match interpolation {
+        Interpolation::Nearest => 9728,
+        Interpolation::Linear => 9729
+    };
+    let min_filter = match (cpu_texture.min_filter, cpu_texture.mip_map_filter) {
+        (min_filter, None) => filter(min_filter),
+        (Interpolation::Nearest, Some(Interpolation::Nearest)) => 9984,
+        (Interpolation::Linear, Some(Interpolation::Nearest)) => 9985,
+        (Interpolation::Nearest, Some(Interpolation::Linear)) => 9986,
+        (Interpolation::Linear, Some(Interpolation::Linear)) => 9987
+    };
+    let wrapping = |wrapping: Wrapping| match wrapping {
+        Wrapping::ClampToEdge => 33071,
+        Wrapping::MirroredRepeat => 33648,
+        Wrapping::Repeat => 10497
+    };
+    serde_json::json!({
+        "magFilter": filter(cpu_texture.mag_filter),
+        "minFilter": min_filter,
+        "wrapS": wrapping(cpu_texture.wrap_s),
+        "wrapT": wrapping(cpu_texture.wrap_t)
+    })
+}
+
+fn wrapping_from(mode: ::gltf::texture::WrappingMode) -> Wrapping {
+    match mode {
+        ::gltf::texture::WrappingMode::ClampToEdge => Wrapping::ClampToEdge,
+        ::gltf::texture::WrappingMode::MirroredRepeat => Wrapping::MirroredRepeat,
+        ::gltf::texture::WrappingMode::Repeat => Wrapping::Repeat
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Saver {
+    ///
+    /// Saves the given meshes and materials as a .gltf file.
+    /// The binary data is saved in a .bin file and the texture images in .png files next to the .gltf file.
+    ///
+    /// # Feature
+    /// Only available when the `gltf-io` feature is enabled.
+    ///
+    pub fn save_gltf<P: AsRef<Path>>(path: P, cpu_meshes: &[CPUMesh], cpu_materials: &[CPUMaterial]) -> Result<(), IOError>
+    {
+        let dir = path.as_ref().parent().unwrap_or(Path::new("./"));
+        let filename = path.as_ref().file_stem().unwrap().to_str().unwrap();
+        let mut builder = GltfBuilder::new(Some(percent_encode(&format!("{}.bin", filename))), cpu_materials);
+        for (name, cpu_texture) in builder.textures.clone() {
+            let image_name = format!("{}_{}.png", filename, name);
+            Self::save_file(dir.join(&image_name), &png_from_texture(&cpu_texture)?)?;
+            builder.images.push(serde_json::json!({"uri": percent_encode(&image_name)}));
+        }
+        let json = builder.build(cpu_meshes, cpu_materials)?;
+        Self::save_file(dir.join(format!("{}.gltf", filename)), &serde_json::to_vec_pretty(&json).map_err(map_json_error)?)?;
+        Self::save_file(dir.join(format!("{}.bin", filename)), &builder.buffer)?;
+        Ok(())
+    }
+
+    ///
+    /// Saves the given meshes and materials as a binary .glb file with all data, including the texture images, embedded.
+    ///
+    /// # Feature
+    /// Only available when the `gltf-io` feature is enabled.
+    ///
+    pub fn save_glb<P: AsRef<Path>>(path: P, cpu_meshes: &[CPUMesh], cpu_materials: &[CPUMaterial]) -> Result<(), IOError>
+    {
+        let mut builder = GltfBuilder::new(None, cpu_materials);
+        for (_, cpu_texture) in builder.textures.clone() {
+            let view = builder.push_view(&png_from_texture(&cpu_texture)?, None);
+            builder.images.push(serde_json::json!({"bufferView": view, "mimeType": "image/png"}));
+        }
+        let json = builder.build(cpu_meshes, cpu_materials)?;
+        let glb = ::gltf::binary::Glb {
+            header: ::gltf::binary::Header { magic: *b"glTF", version: 2, length: 0 },
+            json: std::borrow::Cow::Owned(serde_json::to_vec(&json).map_err(map_json_error)?),
+            bin: if !builder.buffer.is_empty() { Some(std::borrow::Cow::Owned(builder.buffer)) } else { None }
+        };
+        Self::save_file(path, &glb.to_vec()?)?;
+        Ok(())
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+struct GltfBuilder {
+    uri: Option<String>,
+    buffer: Vec<u8>,
+    buffer_views: Vec<serde_json::Value>,
+    accessors: Vec<serde_json::Value>,
+    images: Vec<serde_json::Value>,
+    textures: Vec<(String, Rc<CPUTexture<u8>>)>
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl GltfBuilder {
+    const ARRAY_BUFFER: u32 = 34962;
+    const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+    const UNSIGNED_BYTE: u32 = 5121;
+    const UNSIGNED_INT: u32 = 5125;
+    const FLOAT: u32 = 5126;
+
+    fn new(uri: Option<String>, cpu_materials: &[CPUMaterial]) -> Self {
+        // Each texture is only saved once, also when it is shared between materials, and is named after the first material using it
+        let mut textures: Vec<(String, Rc<CPUTexture<u8>>)> = Vec::new();
+        for cpu_material in cpu_materials.iter() {
+            for (suffix, cpu_texture) in [("", &cpu_material.texture_image), ("_normal", &cpu_material.bump_image)].iter() {
+                if let Some(cpu_texture) = cpu_texture {
+                    if !textures.iter().any(|(_, t)| Rc::ptr_eq(t, cpu_texture)) {
+                        textures.push((format!("{}{}", cpu_material.name, suffix), cpu_texture.clone()));
+                    }
+                }
+            }
+        }
+        Self { uri, buffer: Vec::new(), buffer_views: Vec::new(), accessors: Vec::new(), images: Vec::new(), textures }
+    }
+
+    fn texture_index(&self, cpu_texture: &Rc<CPUTexture<u8>>) -> usize {
+        self.textures.iter().position(|(_, t)| Rc::ptr_eq(t, cpu_texture)).unwrap()
+    }
+
+    fn build(&mut self, cpu_meshes: &[CPUMesh], cpu_materials: &[CPUMaterial]) -> Result<serde_json::Value, IOError> {
+        let mut materials = Vec::new();
+        for cpu_material in cpu_materials.iter() {
+            let color = cpu_material.color.unwrap_or((1.0, 1.0, 1.0, 1.0));
+            let specular_power = cpu_material.specular_power.unwrap_or(6.0).max(0.0);
+            let mut pbr = serde_json::json!({
+                "metallicFactor": 0.0,
+                "roughnessFactor": (2.0 / (specular_power + 2.0)).sqrt()
+            });
+            if let Some(ref cpu_texture) = cpu_material.texture_image {
+                // The color is not used when the material is textured, so only keep the transparency
+                pbr["baseColorFactor"] = serde_json::json!([1.0, 1.0, 1.0, color.3]);
+                pbr["baseColorTexture"] = serde_json::json!({"index": self.texture_index(cpu_texture)});
+            } else {
+                pbr["baseColorFactor"] = serde_json::json!([color.0, color.1, color.2, color.3]);
+            }
+            let mut material = serde_json::json!({"name": cpu_material.name, "pbrMetallicRoughness": pbr});
+            if color.3 < 1.0 {
+                material["alphaMode"] = serde_json::json!("BLEND");
+            }
+            if let Some(ref cpu_texture) = cpu_material.bump_image {
+                material["normalTexture"] = serde_json::json!({"index": self.texture_index(cpu_texture)});
+            }
+            if let Some(emissive_color) = cpu_material.emissive_color {
+                material["emissiveFactor"] = serde_json::json!([emissive_color.0, emissive_color.1, emissive_color.2]);
+            }
+            materials.push(material);
+        }
+
+        let mut meshes = Vec::new();
+        let mut nodes = Vec::new();
+        for cpu_mesh in cpu_meshes.iter() {
+            let vertex_count = cpu_mesh.positions.len() / 3;
+            let aabb = cpu_mesh.compute_aabb();
+            let view = self.push_view(&f32_bytes(&cpu_mesh.positions), Some(Self::ARRAY_BUFFER));
+            let mut attributes = serde_json::json!({
+                "POSITION": self.push_accessor(view, Self::FLOAT, vertex_count, "VEC3", false,
+                    Some((vec![aabb.min.x, aabb.min.y, aabb.min.z], vec![aabb.max.x, aabb.max.y, aabb.max.z])))
+            });
+            if let Some(ref normals) = cpu_mesh.normals {
+                let view = self.push_view(&f32_bytes(normals), Some(Self::ARRAY_BUFFER));
+                attributes["NORMAL"] = self.push_accessor(view, Self::FLOAT, normals.len() / 3, "VEC3", false, None).into();
+            }
+            if let Some(ref tangents) = cpu_mesh.tangents {
+                // The handedness is flipped since the v coordinate is flipped
+                let flipped_tangents: Vec<f32> = tangents.chunks(4).flat_map(|t| vec![t[0], t[1], t[2], -t[3]]).collect();
+                let view = self.push_view(&f32_bytes(&flipped_tangents), Some(Self::ARRAY_BUFFER));
+                attributes["TANGENT"] = self.push_accessor(view, Self::FLOAT, tangents.len() / 4, "VEC4", false, None).into();
+            }
+            if let Some(ref uvs) = cpu_mesh.uvs {
+                let flipped_uvs: Vec<f32> = uvs.chunks(2).flat_map(|uv| vec![uv[0], 1.0 - uv[1]]).collect();
+                let view = self.push_view(&f32_bytes(&flipped_uvs), Some(Self::ARRAY_BUFFER));
+                attributes["TEXCOORD_0"] = self.push_accessor(view, Self::FLOAT, uvs.len() / 2, "VEC2", false, None).into();
+            }
+            if let Some(ref colors) = cpu_mesh.colors {
+                let view = self.push_view(colors, Some(Self::ARRAY_BUFFER));
+                attributes["COLOR_0"] = self.push_accessor(view, Self::UNSIGNED_BYTE, colors.len() / 4, "VEC4", true, None).into();
+            }
+
+            let mut primitive = serde_json::json!({"attributes": attributes});
+            match cpu_mesh.primitive_type {
+                PrimitiveType::Triangles => {},
+                PrimitiveType::Lines => primitive["mode"] = 1.into(),
+                PrimitiveType::Points => primitive["mode"] = 0.into()
+            }
+            if let Some(ref indices) = cpu_mesh.indices {
+                let bytes: Vec<u8> = indices.iter().flat_map(|i| i.to_le_bytes().to_vec()).collect();
+                let view = self.push_view(&bytes, Some(Self::ELEMENT_ARRAY_BUFFER));
+                primitive["indices"] = self.push_accessor(view, Self::UNSIGNED_INT, indices.len(), "SCALAR", false, None).into();
+            }
+            if let Some(ref material_name) = cpu_mesh.material_name {
+                let index = cpu_materials.iter().position(|m| &m.name == material_name).ok_or(
+                    IOError::FailedToSave {message: format!("The mesh {} refers to the material {} which is not saved.", cpu_mesh.name, material_name)})?;
+                primitive["material"] = index.into();
+            }
+            nodes.push(serde_json::json!({"name": cpu_mesh.name, "mesh": meshes.len()}));
+            meshes.push(serde_json::json!({"name": cpu_mesh.name, "primitives": [primitive]}));
+        }
+
+        let mut buffer = serde_json::json!({"byteLength": self.buffer.len()});
+        if let Some(ref uri) = self.uri {
+            buffer["uri"] = uri.clone().into();
+        }
+        let mut root = serde_json::json!({
+            "asset": {"version": "2.0", "generator": "three-d"},
+            "scene": 0,
+            "scenes": [{"nodes": (0..nodes.len()).collect::<Vec<_>>()}],
+            "nodes": nodes,
+            "meshes": meshes,
+            "materials": materials,
+            "accessors": self.accessors,
+            "bufferViews": self.buffer_views,
+            "buffers": if !self.buffer.is_empty() { vec![buffer] } else { vec![] }
+        });
+        if !self.images.is_empty() {
+            // Textures with the same filtering and wrapping share a sampler
+            let mut samplers = Vec::new();
+            let mut textures = Vec::new();
+            for (i, (_, cpu_texture)) in self.textures.iter().enumerate() {
+                let sampler = sampler_json(cpu_texture);
+                let index = samplers.iter().position(|s| *s == sampler).unwrap_or_else(|| {
+                    samplers.push(sampler);
+                    samplers.len() - 1
+                });
+                textures.push(serde_json::json!({"source": i, "sampler": index}));
+            }
+            root["textures"] = textures.into();
+            root["images"] = self.images.clone().into();
+            root["samplers"] = samplers.into();
+        }
+        // Empty arrays are not allowed by the glTF specification
+        if let Some(object) = root.as_object_mut() {
+            object.retain(|_, value| value.as_array().map(|a| !a.is_empty()).unwrap_or(true));
+        }
+        Ok(root)
+    }
+
+    fn push_view(&mut self, bytes: &[u8], target: Option<u32>) -> usize {
+        let mut view = serde_json::json!({"buffer": 0, "byteOffset": self.buffer.len(), "byteLength": bytes.len()});
+        if let Some(target) = target {
+            view["target"] = target.into();
+        }
+        self.buffer.extend_from_slice(bytes);
+        while self.buffer.len() % 4 != 0 {
+            self.buffer.push(0);
+        }
+        self.buffer_views.push(view);
+        self.buffer_views.len() - 1
+    }
+
+    fn push_accessor(&mut self, view: usize, component_type: u32, count: usize, accessor_type: &str, normalized: bool, min_max: Option<(Vec<f32>, Vec<f32>)>) -> usize {
+        let mut accessor = serde_json::json!({"bufferView": view, "componentType": component_type, "count": count, "type": accessor_type});
+        if normalized {
+            accessor["normalized"] = true.into();
+        }
+        if let Some((min, max)) = min_max {
+            accessor["min"] = min.into();
+            accessor["max"] = max.into();
+        }
+        self.accessors.push(accessor);
+        self.accessors.len() - 1
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn f32_bytes(values: &[f32]) -> Vec<u8> {
+    values.iter().flat_map(|v| v.to_le_bytes().to_vec()).collect()
+}
+
+// Encodes the characters which are not allowed in an uri, for example spaces in a file name
+#[cfg(not(target_arch = "wasm32"))]
+fn percent_encode(text: &str) -> String {
+    text.bytes().map(|b| match b {
+        b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+        _ => format!("%{:02X}", b)
+    }).collect()
+}
+
+fn percent_decode(uri: &str) -> String {
+    let bytes = uri.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let code = if bytes[i] == b'%' { uri.get(i + 1..i + 3).and_then(|hex| u8::from_str_radix(hex, 16).ok()) } else { None };
+        if let Some(code) = code {
+            decoded.push(code);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn map_json_error(e: serde_json::Error) -> IOError {
+    IOError::FailedToSave {message: format!("Could not serialize glTF json: {}", e)}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn triangle_gltf(buffer_uri: Option<String>, byte_length: usize) -> serde_json::Value {
+        let mut buffer = serde_json::json!({"byteLength": byte_length});
+        if let Some(uri) = buffer_uri {
+            buffer["uri"] = uri.into();
+        }
+        serde_json::json!({
+            "asset": {"version": "2.0"},
+            "scene": 1,
+            "scenes": [{"nodes": [0, 1]}, {"nodes": [0]}],
+            "nodes": [{"name": "triangle", "mesh": 0, "translation": [1.0, 0.0, 0.0]}, {"name": "other", "mesh": 0}],
+            "meshes": [{"name": "triangle", "primitives": [{"attributes": {"POSITION": 0}}]}],
+            "accessors": [{"bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3", "min": [0.0, 0.0, 0.0], "max": [1.0, 1.0, 0.0]}],
+            "bufferViews": [{"buffer": 0, "byteOffset": 0, "byteLength": byte_length}],
+            "buffers": [buffer]
+        })
+    }
+
+    fn triangle_positions() -> Vec<u8> {
+        f32_bytes(&[0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0])
+    }
+
+    fn load(path: &str, bytes: Vec<u8>) -> (Vec<CPUMesh>, Vec<CPUMaterial>) {
+        let mut loaded = HashMap::new();
+        loaded.insert(std::path::PathBuf::from(path), Ok(bytes));
+        Loaded::new(loaded).gltf(path).unwrap()
+    }
+
+    #[test]
+    fn load_gltf_with_data_uri() {
+        let positions = triangle_positions();
+        let uri = format!("data:application/octet-stream;base64,{}", base64::encode(&positions));
+        let json = triangle_gltf(Some(uri), positions.len());
+        let (cpu_meshes, cpu_materials) = load("triangle.gltf", serde_json::to_vec(&json).unwrap());
+
+        // Only the default scene is loaded, even though the node is also used in another scene
+        assert_eq!(cpu_meshes.len(), 1);
+        assert!(cpu_materials.is_empty());
+        assert_eq!(cpu_meshes[0].name, "triangle");
+        assert_eq!(cpu_meshes[0].positions, vec![1.0, 0.0, 0.0, 2.0, 0.0, 0.0, 1.0, 1.0, 0.0]);
+        assert_eq!(cpu_meshes[0].indices, None);
+    }
+
+    #[test]
+    fn load_glb() {
+        let positions = triangle_positions();
+        let mut json = triangle_gltf(None, positions.len());
+        json.as_object_mut().unwrap().remove("scene");
+        let glb = ::gltf::binary::Glb {
+            header: ::gltf::binary::Header { magic: *b"glTF", version: 2, length: 0 },
+            json: std::borrow::Cow::Owned(serde_json::to_vec(&json).unwrap()),
+            bin: Some(std::borrow::Cow::Owned(positions))
+        };
+        let (cpu_meshes, _) = load("triangle.glb", glb.to_vec().unwrap());
+
+        // Without a default scene, the first scene is loaded
+        assert_eq!(cpu_meshes.len(), 2);
+        assert_eq!(cpu_meshes[0].positions, vec![1.0, 0.0, 0.0, 2.0, 0.0, 0.0, 1.0, 1.0, 0.0]);
+        assert_eq!(cpu_meshes[1].name, "triangle");
+        assert_eq!(cpu_meshes[1].positions, vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0]);
+    }
+
+    #[test]
+    fn load_invalid_buffer_view() {
+        let positions = triangle_positions();
+        let uri = format!("data:application/octet-stream;base64,{}", base64::encode(&positions));
+        let mut json = triangle_gltf(Some(uri), positions.len());
+        json["bufferViews"][0]["byteOffset"] = 4.into();
+        let mut loaded = HashMap::new();
+        loaded.insert(std::path::PathBuf::from("triangle.gltf"), Ok(serde_json::to_vec(&json).unwrap()));
+        match Loaded::new(loaded).gltf("triangle.gltf") {
+            Err(IOError::FailedToLoad {message}) => assert!(message.contains("outside of the buffer")),
+            result => panic!("Expected an error, got {:?}", result.map(|_| ()))
+        }
+    }
+
+    #[test]
+    fn load_mirrored_with_incomplete_triangle() {
+        let mut bytes = triangle_positions();
+        bytes.extend([0u32, 1, 2, 0].iter().flat_map(|i| i.to_le_bytes().to_vec()));
+        let uri = format!("data:application/octet-stream;base64,{}", base64::encode(&bytes));
+        let mut json = triangle_gltf(Some(uri), bytes.len());
+        json["nodes"][1]["scale"] = serde_json::json!([-1.0, 1.0, 1.0]);
+        json["scene"] = 0.into();
+        json["meshes"][0]["primitives"][0]["indices"] = 1.into();
+        json["accessors"][0]["bufferView"] = 0.into();
+        json["accessors"].as_array_mut().unwrap().push(serde_json::json!({"bufferView": 1, "componentType": 5125, "count": 4, "type": "SCALAR"}));
+        json["bufferViews"] = serde_json::json!([{"buffer": 0, "byteOffset": 0, "byteLength": 36}, {"buffer": 0, "byteOffset": 36, "byteLength": 16}]);
+        let (cpu_meshes, _) = load("triangle.gltf", serde_json::to_vec(&json).unwrap());
+        // The winding order is flipped by the mirroring and the incomplete triangle at the end is ignored
+        assert_eq!(cpu_meshes[1].positions, vec![0.0, 0.0, 0.0, -1.0, 0.0, 0.0, 0.0, 1.0, 0.0]);
+        assert_eq!(cpu_meshes[1].indices, Some(vec![0, 2, 1]));
+    }
+
+    #[test]
+    fn load_line_loop() {
+        let positions = triangle_positions();
+        let uri = format!("data:application/octet-stream;base64,{}", base64::encode(&positions));
+        let mut json = triangle_gltf(Some(uri), positions.len());
+        json["meshes"][0]["primitives"][0]["mode"] = 2.into();
+        let (cpu_meshes, _) = load("triangle.gltf", serde_json::to_vec(&json).unwrap());
+        assert_eq!(cpu_meshes[0].primitive_type, PrimitiveType::Lines);
+        assert_eq!(cpu_meshes[0].indices, Some(vec![0, 1, 1, 2, 2, 0]));
+    }
+
+    fn test_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("three-d-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn load_dir(dir: &Path) -> Loaded {
+        let mut loaded = HashMap::new();
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            loaded.insert(path.clone(), Ok(std::fs::read(&path).unwrap()));
+        }
+        Loaded::new(loaded)
+    }
+
+    fn materials() -> Vec<CPUMaterial> {
+        let texture = Rc::new(CPUTexture {data: vec![255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255], width: 2, height: 2,
+            format: Format::RGB8, ..Default::default()});
+        let normal_map = Rc::new(CPUTexture {data: vec![128, 128, 255], width: 1, height: 1, format: Format::RGB8,
+            min_filter: Interpolation::Nearest, mag_filter: Interpolation::Nearest, mip_map_filter: Some(Interpolation::Nearest),
+            wrap_s: Wrapping::ClampToEdge, wrap_t: Wrapping::MirroredRepeat, ..Default::default()});
+        vec![
+            CPUMaterial {name: "first material".to_string(), texture_image: Some(texture.clone()), bump_image: Some(normal_map),
+                emissive_color: Some((0.5, 0.25, 0.0)), ..Default::default()},
+            CPUMaterial {name: "second".to_string(), texture_image: Some(texture), ..Default::default()},
+            CPUMaterial {name: "third".to_string(), color: Some((0.0, 0.5, 1.0, 1.0)), ..Default::default()}
+        ]
+    }
+
+    fn meshes() -> Vec<CPUMesh> {
+        vec![CPUMesh {
+            name: "quad".to_string(),
+            material_name: Some("first material".to_string()),
+            positions: vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 1.0, 0.0],
+            indices: Some(vec![0, 1, 2, 0, 2, 3]),
+            normals: Some(vec![0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0]),
+            uvs: Some(vec![0.0, 0.0, 1.0, 0.0, 1.0, 1.0, 0.0, 1.0]),
+            colors: Some(vec![255, 0, 0, 255, 0, 255, 0, 255, 0, 0, 255, 255, 255, 255, 255, 255]),
+            ..Default::default()
+        }]
+    }
+
+    fn check_round_trip(cpu_meshes: &[CPUMesh], cpu_materials: &[CPUMaterial]) {
+        let expected = meshes();
+        assert_eq!(cpu_meshes.len(), 1);
+        assert_eq!(cpu_meshes[0].name, expected[0].name);
+        assert_eq!(cpu_meshes[0].material_name, expected[0].material_name);
+        assert_eq!(cpu_meshes[0].positions, expected[0].positions);
+        assert_eq!(cpu_meshes[0].indices, expected[0].indices);
+        assert_eq!(cpu_meshes[0].normals, expected[0].normals);
+        assert_eq!(cpu_meshes[0].uvs, expected[0].uvs);
+        assert_eq!(cpu_meshes[0].colors, expected[0].colors);
+
+        let expected = materials();
+        assert_eq!(cpu_materials.len(), 3);
+        assert_eq!(cpu_materials[0].name, "first material");
+        assert_eq!(cpu_materials[0].emissive_color, Some((0.5, 0.25, 0.0)));
+        assert_eq!(cpu_materials[0].texture_image.as_ref().unwrap().data, expected[0].texture_image.as_ref().unwrap().data);
+        assert_eq!(cpu_materials[0].bump_image.as_ref().unwrap().data, expected[0].bump_image.as_ref().unwrap().data);
+        for (cpu_texture, expected_texture) in [(&cpu_materials[0].texture_image, &expected[0].texture_image), (&cpu_materials[0].bump_image, &expected[0].bump_image)].iter() {
+            let (cpu_texture, expected_texture) = (cpu_texture.as_ref().unwrap(), expected_texture.as_ref().unwrap());
+            assert_eq!((cpu_texture.min_filter, cpu_texture.mag_filter, cpu_texture.mip_map_filter),
+                       (expected_texture.min_filter, expected_texture.mag_filter, expected_texture.mip_map_filter));
+            assert_eq!((cpu_texture.wrap_s, cpu_texture.wrap_t), (expected_texture.wrap_s, expected_texture.wrap_t));
+        }
+        assert!(Rc::ptr_eq(cpu_materials[0].texture_image.as_ref().unwrap(), cpu_materials[1].texture_image.as_ref().unwrap()));
+        assert_eq!(cpu_materials[1].emissive_color, None);
+        assert!(cpu_materials[1].bump_image.is_none());
+        assert_eq!(cpu_materials[2].color, Some((0.0, 0.5, 1.0, 1.0)));
+        assert!(cpu_materials[2].texture_image.is_none());
+    }
+
+    #[test]
+    fn save_and_load_gltf() {
+        let dir = test_dir("gltf");
+        Saver::save_gltf(dir.join("my model.gltf"), &meshes(), &materials()).unwrap();
+
+        // The texture shared between the first and second material is only saved once
+        let mut files: Vec<String> = std::fs::read_dir(&dir).unwrap().map(|entry| entry.unwrap().file_name().into_string().unwrap()).collect();
+        files.sort();
+        assert_eq!(files, vec!["my model.bin", "my model.gltf", "my model_first material.png", "my model_first material_normal.png"]);
+        let json: serde_json::Value = serde_json::from_slice(&std::fs::read(dir.join("my model.gltf")).unwrap()).unwrap();
+        assert_eq!(json["images"][0]["uri"], "my%20model_first%20material.png");
+        assert_eq!(json["samplers"].as_array().unwrap().len(), 2);
+
+        let (cpu_meshes, cpu_materials) = load_dir(&dir).gltf(dir.join("my model.gltf")).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        check_round_trip(&cpu_meshes, &cpu_materials);
+    }
+
+    #[test]
+    fn save_and_load_glb() {
+        let dir = test_dir("glb");
+        Saver::save_glb(dir.join("model.glb"), &meshes(), &materials()).unwrap();
+        let (cpu_meshes, cpu_materials) = load_dir(&dir).gltf(dir.join("model.glb")).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        check_round_trip(&cpu_meshes, &cpu_materials);
+    }
+
+    #[test]
+    fn save_and_load_lines() {
+        let dir = test_dir("gltf-lines");
+        let lines = CPUMesh {name: "lines".to_string(), primitive_type: PrimitiveType::Lines, positions: meshes()[0].positions.clone(),
+            indices: Some(vec![0, 1, 2, 3]), ..Default::default()};
+        Saver::save_glb(dir.join("lines.glb"), &[lines], &[]).unwrap();
+        let (cpu_meshes, _) = load_dir(&dir).gltf(dir.join("lines.glb")).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(cpu_meshes[0].primitive_type, PrimitiveType::Lines);
+        assert_eq!(cpu_meshes[0].indices, Some(vec![0, 1, 2, 3]));
+    }
+
+    #[test]
+    fn percent_encoding() {
+        assert_eq!(percent_encode("a b#c.png"), "a%20b%23c.png");
+        assert_eq!(percent_decode("a%20b%23c.png"), "a b#c.png");
+        assert_eq!(percent_decode("100%.png"), "100%.png");
+    }
+}
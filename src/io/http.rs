@@ -0,0 +1,242 @@
+use crate::io::*;
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::Path;
+use std::time::Duration;
+
+const MAX_REDIRECTS: usize = 5;
+// The maximum time to wait for connecting and for each read and write, so a stalled server does not block the loader forever
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+// Returns true if the path is an URL which should be downloaded instead of read from disk.
+// HTTPS URLs are also URLs, but fetching them fails since only plain HTTP is supported on desktop.
+pub(crate) fn is_url(path: &Path) -> bool {
+    path.to_str().map(|path| path.starts_with("http://") || path.starts_with("https://")).unwrap_or(false)
+}
+
+// Downloads the resource at the given URL using a HTTP/1.1 GET request, following redirects
+pub(crate) fn fetch(url: &str) -> Result<Vec<u8>, IOError> {
+    let mut url = url.to_string();
+    for _ in 0..=MAX_REDIRECTS {
+        if url.starts_with("https://") {
+            Err(IOError::FailedToFetch {url: url.clone(), status: None,
+                message: "HTTPS is not supported on desktop, only plain HTTP.".to_string()})?;
+        }
+        match get(&url)? {
+            Response::Ok(bytes) => return Ok(bytes),
+            Response::Redirect(location) => url = resolve_location(&url, &location)?
+        }
+    }
+    Err(IOError::FailedToFetch {url, status: None, message: format!("Too many redirects (more than {}).", MAX_REDIRECTS)})
+}
+
+// Resolves the location of a redirect, which can be relative to the URL of the request
+fn resolve_location(url: &str, location: &str) -> Result<String, IOError> {
+    Ok(if location.starts_with("http://") || location.starts_with("https://") {
+        location.to_string()
+    } else if location.starts_with("//") {
+        format!("http:{}", location)
+    } else {
+        let (host, path) = split_url(url)?;
+        let path = if location.starts_with('/') {
+            location.to_string()
+        } else {
+            // Relative to the directory of the current path, ignoring the query
+            let path = path.split(['?', '#']).next().unwrap_or_default();
+            let directory = path.rfind('/').map(|i| &path[..i + 1]).unwrap_or("");
+            format!("{}{}", directory, location)
+        };
+        // Remove the `.` and `..` segments
+        let mut segments: Vec<&str> = Vec::new();
+        for segment in path.trim_start_matches('/').split('/') {
+            match segment {
+                "." => {},
+                ".." => { segments.pop(); },
+                _ => segments.push(segment)
+            }
+        }
+        format!("http://{}/{}", host, segments.join("/"))
+    })
+}
+
+enum Response {
+    Ok(Vec<u8>),
+    Redirect(String)
+}
+
+fn get(url: &str) -> Result<Response, IOError> {
+    let error = |status: Option<u16>, message: String| IOError::FailedToFetch {url: url.to_string(), status, message};
+    let (host, path) = split_url(url)?;
+    let address = if host.contains(':') { host.to_string() } else { format!("{}:80", host) };
+
+    let connect_error = |e: std::io::Error| error(None, format!("Could not connect to {}: {}", address, e));
+    let mut last_error = None;
+    let mut stream = None;
+    for socket_address in address.to_socket_addrs().map_err(connect_error)? {
+        match TcpStream::connect_timeout(&socket_address, TIMEOUT) {
+            Ok(s) => { stream = Some(s); break; },
+            Err(e) => last_error = Some(e)
+        }
+    }
+    let mut stream = stream.ok_or_else(|| connect_error(last_error.unwrap_or_else(||
+        std::io::Error::new(std::io::ErrorKind::NotFound, "the host could not be resolved"))))?;
+    stream.set_read_timeout(Some(TIMEOUT)).and_then(|_| stream.set_write_timeout(Some(TIMEOUT)))
+        .map_err(|e| error(None, format!("Could not set the timeout: {}", e)))?;
+    let request = format!("GET /{} HTTP/1.1\r\nHost: {}\r\nAccept: application/octet-stream\r\nConnection: close\r\nUser-Agent: three-d\r\n\r\n", path, host);
+    stream.write_all(request.as_bytes()).map_err(|e| error(None, format!("Could not send the request: {}", e)))?;
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response).map_err(|e| error(None, format!("Could not read the response: {}", e)))?;
+
+    let header_end = response.windows(4).position(|w| w == b"\r\n\r\n")
+        .ok_or_else(|| error(None, "The response does not contain a complete header.".to_string()))?;
+    let header = String::from_utf8_lossy(&response[..header_end]).to_string();
+    let body = &response[header_end + 4..];
+    let mut lines = header.lines();
+    let status_line = lines.next().unwrap_or_default();
+    let mut parts = status_line.splitn(3, ' ');
+    let status = parts.nth(1).and_then(|status| status.parse::<u16>().ok())
+        .ok_or_else(|| error(None, format!("Invalid status line '{}'.", status_line)))?;
+    let reason = parts.next().unwrap_or_default().to_string();
+    let header_value = |name: &str| header.lines().skip(1)
+        .filter_map(|line| line.split_once(':'))
+        .find(|(key, _)| key.trim().eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.trim().to_string());
+
+    match status {
+        200..=299 => {},
+        301 | 302 | 303 | 307 | 308 => {
+            return header_value("Location").map(Response::Redirect)
+                .ok_or_else(|| error(Some(status), "Redirect without a location.".to_string()));
+        },
+        _ => Err(error(Some(status), reason))?
+    }
+
+    let is_chunked = header_value("Transfer-Encoding").map(|value| value.eq_ignore_ascii_case("chunked")).unwrap_or(false);
+    let bytes = if is_chunked {
+        decode_chunked(body).ok_or_else(|| error(Some(status), "Invalid chunked response.".to_string()))?
+    } else if let Some(length) = header_value("Content-Length").and_then(|value| value.parse::<usize>().ok()) {
+        body.get(..length).ok_or_else(|| error(Some(status), format!("Expected {} bytes but only received {}.", length, body.len())))?.to_vec()
+    } else {
+        body.to_vec()
+    };
+    Ok(Response::Ok(bytes))
+}
+
+// Splits an URL into the host (including the port if specified) and the path without the leading '/'
+fn split_url(url: &str) -> Result<(&str, &str), IOError> {
+    let rest = url.trim_start_matches("http://").trim_start_matches("https://");
+    let (host, path) = rest.split_once('/').unwrap_or((rest, ""));
+    if host.is_empty() {
+        Err(IOError::FailedToFetch {url: url.to_string(), status: None, message: "The URL does not contain a host.".to_string()})?;
+    }
+    Ok((host, path))
+}
+
+fn decode_chunked(mut body: &[u8]) -> Option<Vec<u8>> {
+    let mut bytes = Vec::new();
+    loop {
+        let line_end = body.windows(2).position(|w| w == b"\r\n")?;
+        let size_text = String::from_utf8_lossy(&body[..line_end]).to_string();
+        let size = usize::from_str_radix(size_text.split(';').next()?.trim(), 16).ok()?;
+        body = &body[line_end + 2..];
+        if size == 0 {
+            return Some(bytes);
+        }
+        bytes.extend_from_slice(body.get(..size)?);
+        body = body.get(size + 2..)?;
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    ///
+    /// Starts a HTTP server on a free local port which responds to a request for a path with the given raw response,
+    /// or with 404 Not Found if the path is unknown, and returns the URL of the server.
+    ///
+    pub(crate) fn serve(responses: Vec<(&'static str, Vec<u8>)>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut request = Vec::new();
+                let mut buffer = [0u8; 1024];
+                while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                    let count = stream.read(&mut buffer).unwrap();
+                    if count == 0 { break; }
+                    request.extend_from_slice(&buffer[..count]);
+                }
+                let request = String::from_utf8_lossy(&request).to_string();
+                let path = request.split_whitespace().nth(1).unwrap_or_default().to_string();
+                let response = responses.iter().find(|(p, _)| *p == path).map(|(_, response)| response.clone())
+                    .unwrap_or_else(|| b"HTTP/1.1 404 Not Found\r\nContent-Length: 9\r\n\r\nNot found".to_vec());
+                stream.write_all(&response).unwrap();
+            }
+        });
+        url
+    }
+
+    fn server() -> String {
+        serve(vec![
+            ("/model.obj", b"HTTP/1.1 200 OK\r\nContent-Length: 7\r\nContent-Type: text/plain\r\n\r\nv 0 0 0".to_vec()),
+            ("/chunked.bin", b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n4\r\nchun\r\n3;ext=1\r\nked\r\n0\r\n\r\n".to_vec()),
+            ("/assets/old/model.obj", b"HTTP/1.1 301 Moved Permanently\r\nLocation: ../model.obj\r\nContent-Length: 0\r\n\r\n".to_vec()),
+            ("/assets/model.obj", b"HTTP/1.1 302 Found\r\nLocation: /model.obj\r\n\r\n".to_vec()),
+            ("/loop", b"HTTP/1.1 307 Temporary Redirect\r\nLocation: loop\r\n\r\n".to_vec())
+        ])
+    }
+
+    #[test]
+    fn fetch_ok() {
+        let url = server();
+        assert_eq!(fetch(&format!("{}/model.obj", url)).unwrap(), b"v 0 0 0");
+        assert_eq!(fetch(&format!("{}/chunked.bin", url)).unwrap(), b"chunked");
+    }
+
+    #[test]
+    fn fetch_redirect() {
+        let url = server();
+        // A relative redirect to /assets/model.obj followed by an absolute redirect to /model.obj
+        assert_eq!(fetch(&format!("{}/assets/old/model.obj", url)).unwrap(), b"v 0 0 0");
+        match fetch(&format!("{}/loop", url)) {
+            Err(IOError::FailedToFetch {status: None, message, ..}) => assert!(message.contains("Too many redirects")),
+            result => panic!("Expected too many redirects, got {:?}", result)
+        }
+    }
+
+    #[test]
+    fn fetch_not_found() {
+        let url = format!("{}/missing.obj", server());
+        match fetch(&url) {
+            Err(IOError::FailedToFetch {url: failed_url, status: Some(404), message}) => {
+                assert_eq!(failed_url, url);
+                assert_eq!(message, "Not Found");
+            },
+            result => panic!("Expected a 404 error, got {:?}", result)
+        }
+    }
+
+    #[test]
+    fn fetch_https() {
+        assert!(is_url(Path::new("https://example.com/model.obj")));
+        match fetch("https://example.com/model.obj") {
+            Err(IOError::FailedToFetch {status: None, message, ..}) => assert!(message.contains("HTTPS is not supported")),
+            result => panic!("Expected an error, got {:?}", result)
+        }
+    }
+
+    #[test]
+    fn resolve_redirect_location() {
+        let url = "http://example.com/assets/models/cube.obj?version=2";
+        assert_eq!(resolve_location(url, "cube2.obj").unwrap(), "http://example.com/assets/models/cube2.obj");
+        assert_eq!(resolve_location(url, "../cube.obj").unwrap(), "http://example.com/assets/cube.obj");
+        assert_eq!(resolve_location(url, "./textures/../cube.obj").unwrap(), "http://example.com/assets/models/cube.obj");
+        assert_eq!(resolve_location(url, "/cube.obj").unwrap(), "http://example.com/cube.obj");
+        assert_eq!(resolve_location(url, "//cdn.example.com/cube.obj").unwrap(), "http://cdn.example.com/cube.obj");
+        assert_eq!(resolve_location(url, "http://other.com/cube.obj").unwrap(), "http://other.com/cube.obj");
+        assert_eq!(resolve_location("http://example.com", "cube.obj").unwrap(), "http://example.com/cube.obj");
+    }
+}
@@ -1,37 +1,107 @@
 use std::collections::HashMap;
 use std::rc::Rc;
 use std::cell::RefCell;
+use std::sync::{Arc, Mutex};
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Poll, Waker};
 use log::info;
 use std::path::{Path, PathBuf};
 use crate::io::*;
+use crate::definition::*;
 
-type RefLoaded = Rc<RefCell<HashMap<PathBuf, Result<Vec<u8>, std::io::Error>>>>;
+// A load is still in progress while its result is None
+type RefLoaded = Rc<RefCell<HashMap<PathBuf, Option<Result<Vec<u8>, IOError>>>>>;
+
+type VirtualFiles = Rc<HashMap<PathBuf, Rc<[u8]>>>;
+
+thread_local! {
+    // The table is copied when a file is registered while a Loaded still uses it, so a Loaded can keep the table without borrowing it
+    static VIRTUAL_FILES: RefCell<VirtualFiles> = RefCell::new(Rc::new(HashMap::new()));
+}
+
+fn virtual_files() -> VirtualFiles {
+    VIRTUAL_FILES.with(|files| files.borrow().clone())
+}
+
+// Removes `.` components and resolves `..` components so for example `./assets/model.obj`, `assets/model.obj`
+// and `assets/textures/../model.obj` refer to the same virtual file
+fn virtual_path(path: &Path) -> PathBuf {
+    use std::path::Component;
+    let mut components: Vec<Component> = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {},
+            Component::ParentDir if matches!(components.last(), Some(Component::Normal(_))) => { components.pop(); },
+            _ => components.push(component)
+        }
+    }
+    components.iter().collect()
+}
 
 ///
 /// The resources loaded using the [Loader](crate::Loader).
 /// Use the [bytes](crate::Loaded::bytes) function to extract the raw byte array for the loaded resource
 /// or one of the other methods to both extract and deserialize a loaded resource.
 ///
-pub struct Loaded<'a>  {
-    loaded: &'a mut HashMap<PathBuf, Result<Vec<u8>, std::io::Error>>
+pub struct Loaded {
+    loaded: HashMap<PathBuf, Result<Vec<u8>, IOError>>,
+    virtual_files: VirtualFiles,
+    images: RefCell<HashMap<PathBuf, Rc<CPUTexture<u8>>>>
 }
 
-impl<'a> Loaded<'a> {
+impl Loaded {
+
+    pub(crate) fn new(loaded: HashMap<PathBuf, Result<Vec<u8>, IOError>>) -> Self {
+        Self {loaded, virtual_files: virtual_files(), images: RefCell::new(HashMap::new())}
+    }
 
     ///
     /// Returns the loaded byte array for the resource at the given path.
     /// The byte array then has to be deserialized to whatever type this resource is (image, 3D model etc.).
     ///
-    pub fn bytes<P: AsRef<Path>>(&'a self, path: P) -> Result<&'a [u8], IOError> {
+    pub fn bytes<P: AsRef<Path>>(&self, path: P) -> Result<&[u8], IOError> {
+        if !self.loaded.contains_key(path.as_ref()) {
+            if let Some(bytes) = self.virtual_files.get(&virtual_path(path.as_ref())) {
+                return Ok(bytes);
+            }
+        }
         let bytes = self.loaded.get(path.as_ref()).ok_or(
             IOError::FailedToLoad {message:format!("Tried to use a resource which was not loaded: {}", path.as_ref().to_str().unwrap())})?.as_ref()
-            .map_err(|_| IOError::FailedToLoad {message:format!("Could not load resource: {}", path.as_ref().to_str().unwrap())})?;
+            .map_err(|e| match e {
+                IOError::FailedToFetch {url, status, message} => IOError::FailedToFetch {url: url.clone(), status: *status, message: message.clone()},
+                #[cfg(not(target_arch = "wasm32"))]
+                IOError::FailedToReadFile {path, source} => IOError::FailedToReadFile {path: path.clone(), source: std::io::Error::new(source.kind(), source.to_string())},
+                _ => IOError::FailedToLoad {message:format!("Could not load resource {}: {}", path.as_ref().to_str().unwrap(), e)}
+            })?;
         Ok(bytes)
     }
+
+    ///
+    /// Deserialize the loaded image resource at the given path into a [CPUTexture](crate::CPUTexture) like [image](crate::Loaded::image),
+    /// but the image is only decoded the first time and the same shared CPUTexture is returned for subsequent calls with the same path.
+    /// This is used when loading materials, so materials referencing the same image share the CPUTexture
+    /// and therefore also the GPU texture (see [TextureCache](crate::TextureCache)).
+    ///
+    /// # Feature
+    /// Only available when the `image-io` feature is enabled.
+    ///
+    #[cfg(feature = "image-io")]
+    pub fn shared_image<P: AsRef<Path>>(&self, path: P) -> Result<Rc<CPUTexture<u8>>, IOError> {
+        if let Some(cpu_texture) = self.images.borrow().get(path.as_ref()) {
+            return Ok(cpu_texture.clone());
+        }
+        let cpu_texture = Rc::new(self.image(path.as_ref())?);
+        self.images.borrow_mut().insert(path.as_ref().to_path_buf(), cpu_texture.clone());
+        Ok(cpu_texture)
+    }
 }
 
 ///
 /// Functionality for loading any type of resource runtime on both desktop and web.
+/// On web, the paths are fetched relative to the page and can also be URLs.
+/// On desktop, the paths are read from disk, except `http://` URLs which are downloaded when the `http-io` feature is enabled,
+/// so the same loading code works on both desktop and web. Loading a `https://` URL on desktop fails since only plain HTTP is supported.
 ///
 pub struct Loader {}
 
@@ -42,9 +112,12 @@ impl Loader {
     pub fn load<F, P: AsRef<Path>>(paths: &[P], on_done: F)
         where F: 'static + FnOnce(&mut Loaded)
     {
-        Self::load_with_progress(paths, |progress| {
-                    info!("Progress: {}%", 100.0f32 * progress);
-        }, on_done);
+        Self::load_with_progress(paths, Self::log_progress, on_done);
+    }
+
+    fn log_progress(progress: f32)
+    {
+        info!("Progress: {}%", 100.0f32 * progress);
     }
 
     ///
@@ -59,13 +132,128 @@ impl Loader {
     {
         let loads = Rc::new(RefCell::new(HashMap::new()));
         for path in paths {
-            loads.borrow_mut().insert(path.as_ref().to_path_buf(), Ok(Vec::new()));
-            Self::load_file(path,loads.clone());
+            loads.borrow_mut().insert(path.as_ref().to_path_buf(), None);
+            if let Some(bytes) = Self::virtual_file(path.as_ref()) {
+                loads.borrow_mut().insert(path.as_ref().to_path_buf(), Some(Ok(bytes)));
+            } else {
+                Self::load_file(path,loads.clone());
+            }
         }
         info!("Loading started...");
         Self::wait_local(loads.clone(), progress_callback, on_done);
     }
 
+    ///
+    /// Loads all of the resources in the given paths and returns a future which resolves to the [loaded resources](crate::Loaded).
+    /// On desktop, each call starts a number of threads, at most the available parallelism, which read the files and then exit,
+    /// so the calling thread is not blocked while loading,
+    /// and on web, the files are fetched concurrently using [wasm-bindgen-futures](https://crates.io/crates/wasm-bindgen-futures/main.rs).
+    /// Returns an error if any of the resources could not be loaded.
+    ///
+    pub async fn load_async<P: AsRef<Path>>(paths: &[P]) -> Result<Loaded, IOError>
+    {
+        let paths: Vec<PathBuf> = paths.iter().map(|path| path.as_ref().to_path_buf()).collect();
+        let state = Arc::new(Mutex::new(AsyncLoads {remaining: paths.len(), loaded: HashMap::new(), waker: None}));
+        info!("Loading started...");
+        Self::start_loading(paths, state.clone());
+        let mut loaded = LoadFuture {state}.await;
+        info!("Loading done.");
+        if let Some(path) = loaded.iter().find(|(_, result)| result.is_err()).map(|(path, _)| path.clone()) {
+            Err(loaded.remove(&path).unwrap().unwrap_err())?;
+        }
+        Ok(Loaded::new(loaded))
+    }
+
+    ///
+    /// Starts loading all of the resources in the given paths and returns immediately with a [handle](crate::LoadHandle)
+    /// which can be [updated](crate::LoadHandle::update), for example each frame in the render loop, and queried for the status of each resource.
+    /// Resources can then be used as they arrive instead of waiting for all of them to be loaded.
+    ///
+    pub fn load_streaming<P: AsRef<Path>>(paths: &[P]) -> LoadHandle
+    {
+        Self::load_streaming_with_progress(paths, Self::log_progress)
+    }
+
+    ///
+    /// Starts loading all of the resources in the given paths like [load_streaming](Self::load_streaming).
+    /// Will call **progress_callback** each time the [handle](crate::LoadHandle) is [updated](crate::LoadHandle::update) and new resources have arrived.
+    ///
+    pub fn load_streaming_with_progress<G, P>(paths: &[P], progress_callback: G) -> LoadHandle
+        where
+            G: 'static + Fn(f32),
+            P: AsRef<Path>
+    {
+        let paths: Vec<PathBuf> = paths.iter().map(|path| path.as_ref().to_path_buf()).collect();
+        let state = Arc::new(Mutex::new(AsyncLoads {remaining: paths.len(), loaded: HashMap::new(), waker: None}));
+        info!("Loading started...");
+        Self::start_loading(paths.clone(), state.clone());
+        LoadHandle {paths, state, loaded: Loaded::new(HashMap::new()), progress_callback: Box::new(progress_callback)}
+    }
+
+    ///
+    /// Registers the given bytes as a virtual file at the given path, for example a file embedded using `include_bytes!`.
+    /// When a resource at this path is loaded afterwards, the bytes are used instead of loading the resource from disk or the network.
+    /// Virtual files can also be used directly from the [loaded resources](crate::Loaded) without being loaded first,
+    /// so for example the .mtl file and textures of an .obj file are found when they are registered.
+    /// This only applies to files which are registered before the resources are loaded.
+    /// Virtual files are registered for the current thread.
+    ///
+    pub fn register_bytes<P: AsRef<Path>>(path: P, bytes: &[u8])
+    {
+        VIRTUAL_FILES.with(|files| Rc::make_mut(&mut files.borrow_mut()).insert(virtual_path(path.as_ref()), bytes.into()));
+    }
+
+    fn virtual_file(path: &Path) -> Option<Vec<u8>>
+    {
+        virtual_files().get(&virtual_path(path)).map(|bytes| bytes.to_vec())
+    }
+
+    fn start_loading(paths: Vec<PathBuf>, state: Arc<Mutex<AsyncLoads>>)
+    {
+        let mut files = Vec::new();
+        for path in paths {
+            if let Some(bytes) = Self::virtual_file(&path) {
+                state.lock().unwrap().insert(path, Ok(bytes));
+            } else {
+                files.push(path);
+            }
+        }
+        Self::load_files_async(files, state);
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn load_files_async(paths: Vec<PathBuf>, state: Arc<Mutex<AsyncLoads>>)
+    {
+        if paths.is_empty() {
+            return;
+        }
+        let worker_count = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(4).min(paths.len());
+        let queue = Arc::new(Mutex::new(paths));
+        for _ in 0..worker_count {
+            let queue = queue.clone();
+            let state = state.clone();
+            std::thread::spawn(move || {
+                loop {
+                    let path = if let Some(path) = queue.lock().unwrap().pop() { path } else { break };
+                    let result = Self::read_file(&path);
+                    state.lock().unwrap().insert(path, result);
+                }
+            });
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn load_files_async(paths: Vec<PathBuf>, state: Arc<Mutex<AsyncLoads>>)
+    {
+        for path in paths {
+            let state = state.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                let result = Self::fetch(&path).await;
+                state.lock().unwrap().insert(path, result);
+            });
+        }
+    }
+
     fn wait_local<F, G>(loads: RefLoaded, progress_callback: G, on_done: F)
         where
             G: 'static + Fn(f32),
@@ -76,12 +264,7 @@ impl Loader {
             let is_loading = match loads.try_borrow() {
                 Ok(map) => {
                     let total_count = map.len();
-                    let mut count = 0;
-                    for bytes in map.values() {
-                        if bytes.is_err() || bytes.as_ref().unwrap().len() > 0 {
-                            count = count + 1;
-                        }
-                    }
+                    let count = map.values().filter(|result| result.is_some()).count();
                     progress_callback(count as f32 / total_count as f32);
                     count < total_count
                 },
@@ -92,7 +275,8 @@ impl Loader {
                 Self::wait_local(loads, progress_callback, on_done);
             } else {
                 info!("Loading done.");
-                on_done(&mut Loaded{loaded: &mut loads.borrow_mut() });
+                let loaded = std::mem::take(&mut *loads.borrow_mut()).into_iter().filter_map(|(path, result)| Some((path, result?))).collect();
+                on_done(&mut Loaded::new(loaded));
             }
         });
     }
@@ -119,16 +303,18 @@ impl Loader {
     #[cfg(not(target_arch = "wasm32"))]
     fn load_file<P: AsRef<Path>>(path: P, loads: RefLoaded)
     {
-        let file = std::fs::File::open(path.as_ref());
-        match file {
-            Ok(mut f) => {
-                use std::io::prelude::*;
-                let mut bytes = Vec::new();
-                let result = f.read_to_end(&mut bytes).and(Ok(bytes));
-                loads.borrow_mut().insert(path.as_ref().to_path_buf(), result);
-            },
-            Err(e) => {loads.borrow_mut().insert(path.as_ref().to_path_buf(), Err(e));}
+        let result = Self::read_file(path.as_ref());
+        loads.borrow_mut().insert(path.as_ref().to_path_buf(), Some(result));
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn read_file(path: &Path) -> Result<Vec<u8>, IOError>
+    {
+        #[cfg(feature = "http-io")]
+        if crate::io::http::is_url(path) {
+            return crate::io::http::fetch(path.to_str().unwrap());
         }
+        std::fs::read(path).map_err(|source| IOError::FailedToReadFile {path: path.to_path_buf(), source})
     }
 
     #[cfg(target_arch = "wasm32")]
@@ -139,25 +325,300 @@ impl Loader {
 
     #[cfg(target_arch = "wasm32")]
     async fn load_file_async<P: AsRef<Path>>(path: P, loads: RefLoaded)
+    {
+        let result = Self::fetch(path.as_ref()).await;
+        loads.borrow_mut().insert(path.as_ref().to_path_buf(), Some(result));
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    async fn fetch(path: &Path) -> Result<Vec<u8>, IOError>
     {
         use wasm_bindgen::prelude::*;
         use wasm_bindgen::JsCast;
         use wasm_bindgen_futures::JsFuture;
         use web_sys::{Request, RequestInit, RequestMode, Response};
 
+        let url = path.to_string_lossy().to_string();
+        let error = |status: Option<u16>, message: String| IOError::FailedToFetch {url: url.clone(), status, message};
+
         let mut opts = RequestInit::new();
         opts.method("GET");
         opts.mode(RequestMode::Cors);
 
-        let request = Request::new_with_str_and_init(path.as_ref().to_str().unwrap(), &opts).unwrap();
-        request.headers().set("Accept", "application/octet-stream").unwrap();
+        let request = Request::new_with_str_and_init(&url, &opts)
+            .map_err(|e| error(None, format!("Could not create the request: {:?}", e)))?;
+        request.headers().set("Accept", "application/octet-stream")
+            .map_err(|e| error(None, format!("Could not set the request headers: {:?}", e)))?;
 
-        let window = web_sys::window().unwrap();
-        let resp_value = JsFuture::from(window.fetch_with_request(&request)).await.unwrap();
-        let resp: Response = resp_value.dyn_into().unwrap();
+        let window = web_sys::window().ok_or_else(|| error(None, "There is no window to fetch from.".to_string()))?;
+        let resp_value = JsFuture::from(window.fetch_with_request(&request)).await
+            .map_err(|e| error(None, format!("Network error (this could for example be caused by CORS): {:?}", e)))?;
+        let resp: Response = resp_value.dyn_into()
+            .map_err(|_| error(None, "The fetch did not return a response.".to_string()))?;
+        if let Some(e) = status_error(&url, resp.status(), &resp.status_text()) {
+            Err(e)?;
+        }
 
         // Convert this other `Promise` into a rust `Future`.
-        let data: JsValue = JsFuture::from(resp.array_buffer().unwrap()).await.unwrap();
-        loads.borrow_mut().insert(path.as_ref().to_path_buf(), Ok(js_sys::Uint8Array::new(&data).to_vec()));
+        let data: JsValue = JsFuture::from(resp.array_buffer().map_err(|e| error(Some(resp.status()), format!("The response is not binary data: {:?}", e)))?).await
+            .map_err(|e| error(Some(resp.status()), format!("Could not read the response: {:?}", e)))?;
+        if !data.is_instance_of::<js_sys::ArrayBuffer>() {
+            Err(error(Some(resp.status()), "The response is not binary data.".to_string()))?;
+        }
+        Ok(js_sys::Uint8Array::new(&data).to_vec())
     }
-}
\ No newline at end of file
+}
+
+///
+/// The status of a resource loaded using [Loader::load_streaming](crate::Loader::load_streaming).
+///
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LoadStatus {
+    Pending,
+    Ready,
+    Failed
+}
+
+///
+/// A handle to resources which are being loaded using [Loader::load_streaming](crate::Loader::load_streaming).
+/// The resources which have arrived are collected in the [loaded resources](crate::Loaded) of the handle each time the handle is [updated](Self::update).
+///
+pub struct LoadHandle {
+    paths: Vec<PathBuf>,
+    state: Arc<Mutex<AsyncLoads>>,
+    loaded: Loaded,
+    progress_callback: Box<dyn Fn(f32)>
+}
+
+impl LoadHandle {
+    ///
+    /// Collects the resources which have arrived since the last update and returns their paths.
+    /// Calls the progress callback if any resources have arrived.
+    ///
+    pub fn update(&mut self) -> Vec<PathBuf>
+    {
+        let arrived = std::mem::take(&mut self.state.lock().unwrap().loaded);
+        if arrived.is_empty() {
+            return Vec::new();
+        }
+        let paths = arrived.keys().cloned().collect();
+        self.loaded.loaded.extend(arrived);
+        (self.progress_callback)(self.progress());
+        if self.is_done() {
+            info!("Loading done.");
+        }
+        paths
+    }
+
+    ///
+    /// Returns the [loaded resources](crate::Loaded) which have arrived until the last [update](Self::update).
+    /// The resources which failed to load are also included, so the error can be inspected when using them.
+    /// Since all of the arrived resources are kept, for example an .obj file can be deserialized when its .mtl file and textures have also arrived.
+    ///
+    pub fn loaded(&self) -> &Loaded
+    {
+        &self.loaded
+    }
+
+    ///
+    /// Returns the status of the resource at the given path at the last [update](Self::update).
+    ///
+    pub fn status<P: AsRef<Path>>(&self, path: P) -> LoadStatus
+    {
+        match self.loaded.loaded.get(path.as_ref()) {
+            Some(Ok(_)) => LoadStatus::Ready,
+            Some(Err(_)) => LoadStatus::Failed,
+            None => LoadStatus::Pending
+        }
+    }
+
+    ///
+    /// Returns the fraction of the resources which are either ready or failed at the last [update](Self::update), between 0 and 1.
+    ///
+    pub fn progress(&self) -> f32
+    {
+        if self.paths.is_empty() {
+            return 1.0;
+        }
+        let count = self.paths.iter().filter(|path| self.loaded.loaded.contains_key(*path)).count();
+        count as f32 / self.paths.len() as f32
+    }
+
+    ///
+    /// Returns true when all of the resources are either ready or failed at the last [update](Self::update).
+    ///
+    pub fn is_done(&self) -> bool
+    {
+        self.paths.iter().all(|path| self.loaded.loaded.contains_key(path))
+    }
+}
+
+// Returns the error for a fetch response with the given status, or None if the status is successful.
+// The status text is empty for HTTP/2 responses, so the status code is used as the message in that case.
+#[cfg(any(target_arch = "wasm32", test))]
+fn status_error(url: &str, status: u16, status_text: &str) -> Option<IOError> {
+    if (200..300).contains(&status) {
+        return None;
+    }
+    let message = if status_text.is_empty() { format!("The server responded with status {}.", status) } else { status_text.to_string() };
+    Some(IOError::FailedToFetch {url: url.to_string(), status: Some(status), message})
+}
+
+struct AsyncLoads {
+    remaining: usize,
+    loaded: HashMap<PathBuf, Result<Vec<u8>, IOError>>,
+    waker: Option<Waker>
+}
+
+impl AsyncLoads {
+    fn insert(&mut self, path: PathBuf, result: Result<Vec<u8>, IOError>) {
+        self.loaded.insert(path, result);
+        self.remaining -= 1;
+        if self.remaining == 0 {
+            if let Some(waker) = self.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+struct LoadFuture {
+    state: Arc<Mutex<AsyncLoads>>
+}
+
+impl Future for LoadFuture {
+    type Output = HashMap<PathBuf, Result<Vec<u8>, IOError>>;
+
+    fn poll(self: Pin<&mut Self>, context: &mut std::task::Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.state.lock().unwrap();
+        if state.remaining == 0 {
+            Poll::Ready(std::mem::take(&mut state.loaded))
+        } else {
+            state.waker = Some(context.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load_virtual_file() {
+        Loader::register_bytes("./assets/textures/../model.obj", b"v 0 0 0");
+        Loader::register_bytes("assets/model.mtl", &[1, 2, 3]);
+        let result = Rc::new(RefCell::new(None));
+        let result_clone = result.clone();
+        Loader::load(&["assets/model.obj"], move |loaded| {
+            *result_clone.borrow_mut() = Some((loaded.bytes("assets/model.obj").unwrap().to_vec(), loaded.bytes("assets/model.mtl").unwrap().to_vec()));
+        });
+        assert_eq!(result.borrow_mut().take(), Some((b"v 0 0 0".to_vec(), vec![1, 2, 3])));
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn load_empty_file() {
+        let path = std::env::temp_dir().join(format!("three-d-empty-file-{}.obj", std::process::id()));
+        std::fs::write(&path, b"").unwrap();
+        Loader::register_bytes("assets/empty.obj", b"");
+        let result = Rc::new(RefCell::new(None));
+        let result_clone = result.clone();
+        let load_path = path.clone();
+        Loader::load(&[path.as_path(), Path::new("assets/empty.obj")], move |loaded| {
+            *result_clone.borrow_mut() = Some((loaded.bytes(&load_path).unwrap().len(), loaded.bytes("assets/empty.obj").unwrap().len()));
+        });
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(result.borrow_mut().take(), Some((0, 0)));
+    }
+
+    #[cfg(all(feature = "obj-io", not(target_arch = "wasm32")))]
+    #[test]
+    fn load_streaming() {
+        let dir = std::env::temp_dir().join(format!("three-d-load-streaming-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("model.obj"), "mtllib model.mtl\no triangle\nv 0 0 0\nv 1 0 0\nv 1 1 0\nusemtl red\nf 1 2 3\n").unwrap();
+        std::fs::write(dir.join("model.mtl"), "newmtl red\nKd 1 0 0\n").unwrap();
+        let paths = [dir.join("model.obj"), dir.join("model.mtl"), dir.join("missing.obj")];
+        let progress = Rc::new(RefCell::new(Vec::new()));
+        let progress_clone = progress.clone();
+        let mut handle = Loader::load_streaming_with_progress(&paths, move |progress| progress_clone.borrow_mut().push(progress));
+        let mut arrived = Vec::new();
+        let start = std::time::Instant::now();
+        while !handle.is_done() {
+            assert!(start.elapsed() < std::time::Duration::from_secs(10), "Loading did not finish");
+            arrived.extend(handle.update());
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+        arrived.sort();
+        let mut expected = paths.to_vec();
+        expected.sort();
+        assert_eq!(arrived, expected);
+        assert!(handle.update().is_empty());
+
+        assert_eq!(handle.status(&paths[0]), LoadStatus::Ready);
+        assert_eq!(handle.status(&paths[1]), LoadStatus::Ready);
+        assert_eq!(handle.status(&paths[2]), LoadStatus::Failed);
+        let progress = progress.borrow();
+        assert!(progress.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(progress.last(), Some(&1.0));
+
+        // The .mtl file is found even if it arrived in another update than the .obj file
+        let (_, cpu_materials) = handle.loaded().obj(&paths[0]).unwrap();
+        assert_eq!(cpu_materials[0].name, "red");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn fetch_status_error() {
+        assert!(status_error("model.obj", 200, "OK").is_none());
+        assert!(status_error("model.obj", 204, "").is_none());
+        match status_error("model.obj", 404, "Not Found") {
+            Some(IOError::FailedToFetch {url, status: Some(404), message}) => assert_eq!((url.as_str(), message.as_str()), ("model.obj", "Not Found")),
+            result => panic!("Expected a 404 error, got {:?}", result)
+        }
+        match status_error("model.obj", 503, "") {
+            Some(IOError::FailedToFetch {status: Some(503), message, ..}) => assert!(message.contains("503")),
+            result => panic!("Expected a 503 error, got {:?}", result)
+        }
+        assert!(status_error("model.obj", 304, "Not Modified").is_some());
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn load_missing_file() {
+        let path = std::env::temp_dir().join("three-d-missing-file.obj");
+        let result = Rc::new(RefCell::new(None));
+        let result_clone = result.clone();
+        let load_path = path.clone();
+        Loader::load(&[&path], move |loaded| *result_clone.borrow_mut() = Some(loaded.bytes(&load_path).map(|bytes| bytes.to_vec())));
+        let result = result.borrow_mut().take();
+        match result {
+            Some(Err(IOError::FailedToReadFile {path: failed_path, source})) => {
+                assert_eq!(failed_path, path);
+                assert_eq!(source.kind(), std::io::ErrorKind::NotFound);
+            },
+            result => panic!("Expected a read error, got {:?}", result)
+        }
+    }
+
+    #[cfg(all(feature = "http-io", not(target_arch = "wasm32")))]
+    #[test]
+    fn load_url() {
+        let server = crate::io::http::tests::serve(vec![("/model.obj", b"HTTP/1.1 200 OK\r\nContent-Length: 7\r\n\r\nv 0 0 0".to_vec())]);
+        let (found, missing) = (format!("{}/model.obj", server), format!("{}/missing.obj", server));
+        let result = Rc::new(RefCell::new(None));
+        let result_clone = result.clone();
+        let (found_clone, missing_clone) = (found.clone(), missing.clone());
+        Loader::load(&[&found, &missing], move |loaded| {
+            *result_clone.borrow_mut() = Some((loaded.bytes(&found_clone).map(|bytes| bytes.to_vec()), loaded.bytes(&missing_clone).map(|bytes| bytes.to_vec())));
+        });
+        // The callback is called even though one of the resources could not be loaded
+        let (found_result, missing_result) = result.borrow_mut().take().expect("The callback was not called");
+        assert_eq!(found_result.unwrap(), b"v 0 0 0");
+        match missing_result {
+            Err(IOError::FailedToFetch {url, status: Some(404), ..}) => assert_eq!(url, missing),
+            result => panic!("Expected a 404 error, got {:?}", result)
+        }
+    }
+}
@@ -2,44 +2,61 @@
 use crate::io::*;
 use std::collections::HashMap;
 use std::path::Path;
+use std::rc::Rc;
 use crate::definition::*;
 
-impl<'a> Loaded<'a> {
+impl Loaded {
     ///
     /// Deserialize a loaded .obj file resource and .mtl material file resource (if present) into a list of meshes and materials.
     /// It uses the [wavefront-obj](https://crates.io/crates/wavefront_obj/main.rs) crate.
+    /// Faces with more than three corners are triangulated using ear clipping, so both convex and non-convex polygons are supported.
+    /// Lines and points are not part of the returned meshes, use [obj_lines_and_points](Self::obj_lines_and_points) to load those.
+    /// Normals are computed for the meshes where not all of the face corners specify a normal and uv coordinates are only included if all of the face corners specify them.
     ///
     /// # Feature
     /// Only available when the `obj-io` feature is enabled.
     ///
-    pub fn obj<P: AsRef<Path>>(&'a self, path: P) -> Result<(Vec<CPUMesh>, Vec<CPUMaterial>), IOError> {
-        let obj_bytes = self.bytes(path.as_ref())?;
-        let obj = wavefront_obj::obj::parse(String::from_utf8(obj_bytes.to_owned()).unwrap())?;
+    pub fn obj<P: AsRef<Path>>(&self, path: P) -> Result<(Vec<CPUMesh>, Vec<CPUMaterial>), IOError> {
+        let obj = self.parse_obj(path.as_ref())?;
         let p = path.as_ref().parent().unwrap();
 
         // Parse materials
         let mut cpu_materials = Vec::new();
         if let Some(material_library) = obj.material_library {
-            let bytes = self.bytes(p.join(material_library).to_str().unwrap())?.to_owned();
-            let materials = wavefront_obj::mtl::parse(String::from_utf8(bytes).unwrap())?.materials;
-
-            for material in materials {
-                let color = if material.color_diffuse.r != material.color_diffuse.g || material.color_diffuse.g != material.color_diffuse.b { material.color_diffuse }
-                    else if material.color_specular.r != material.color_specular.g || material.color_specular.g != material.color_specular.b { material.color_specular }
-                    else if material.color_ambient.r != material.color_ambient.g || material.color_ambient.g != material.color_ambient.b { material.color_ambient }
-                    else {material.color_diffuse};
-                let diffuse_intensity = (material.color_diffuse.r as f32).max(material.color_diffuse.g as f32).max(material.color_diffuse.b as f32);
-                let specular_intensity = (material.color_specular.r as f32).max(material.color_specular.g as f32).max(material.color_specular.b as f32);
+            let bytes = self.bytes(p.join(material_library).to_str().unwrap())?;
+            let load_image = |texture_name: &Option<String>| -> Result<Option<Rc<CPUTexture<u8>>>, IOError> {
+                if let Some(texture_name) = texture_name {
+                    Ok(Some(self.shared_image(p.join(texture_name))?))
+                } else {Ok(None)}
+            };
+
+            for material in parse_mtl(&String::from_utf8_lossy(bytes))? {
+                let color = if material.diffuse[0] != material.diffuse[1] || material.diffuse[1] != material.diffuse[2] { material.diffuse }
+                    else if material.specular[0] != material.specular[1] || material.specular[1] != material.specular[2] { material.specular }
+                    else if material.ambient[0] != material.ambient[1] || material.ambient[1] != material.ambient[2] { material.ambient }
+                    else {material.diffuse};
+                let diffuse_intensity = material.diffuse[0].max(material.diffuse[1]).max(material.diffuse[2]);
+                // Illumination model 0 and 1 have no specular highlights and model 0 is a constant color
+                let specular_intensity = match material.illumination {
+                    Some(0) | Some(1) => 0.0,
+                    _ => material.specular[0].max(material.specular[1]).max(material.specular[2])
+                };
+                let emissive_color = match (material.emissive, material.illumination) {
+                    (Some(emissive), _) => Some(emissive),
+                    (None, Some(0)) => Some(color),
+                    _ => None
+                };
                 cpu_materials.push(CPUMaterial {
                     name: material.name,
-                    color: Some((color.r as f32, color.g as f32, color.b as f32, material.alpha as f32)),
-                    diffuse_intensity: Some(diffuse_intensity),
+                    color: Some((color[0], color[1], color[2], material.alpha)),
+                    diffuse_intensity: Some(if material.illumination == Some(0) { 0.0 } else { diffuse_intensity }),
                     specular_intensity: Some(specular_intensity),
-                    specular_power: Some(material.specular_coefficient as f32),
-                    texture_image: if let Some(path) = material.uv_map.as_ref().map(|texture_name| p.join(texture_name).to_str().unwrap().to_owned())
-                    {
-                        Some(self.image(&path)?)
-                    } else {None}
+                    specular_power: Some(material.specular_power),
+                    emissive_color: emissive_color.map(|e| (e[0], e[1], e[2])),
+                    texture_image: load_image(&material.diffuse_map)?,
+                    specular_image: load_image(&material.specular_map)?,
+                    bump_image: load_image(&material.bump_map)?,
+                    opacity_image: load_image(&material.opacity_map)?
                 });
             }
         }
@@ -48,79 +65,635 @@ impl<'a> Loaded<'a> {
         let mut cpu_meshes = Vec::new();
         for object in obj.objects.iter() { // Objects consisting of several meshes with different materials
             for mesh in object.geometry.iter() { // All meshes with different materials
-                let mut positions = Vec::new();
-                let mut normals = Vec::new();
-                let mut uvs = Vec::new();
-                let mut indices = Vec::new();
+                let mut primitives = Vec::new();
+                for shape in mesh.shapes.iter() { // All triangles with same material
+                    if let wavefront_obj::obj::Primitive::Triangle(i0, i1, i2) = shape.primitive {
+                        primitives.push(vec![i0, i1, i2]);
+                    }
+                }
+                if !primitives.is_empty() {
+                    let mut cpu_mesh = obj_mesh(object, mesh.material_name.clone(), PrimitiveType::Triangles, &primitives);
+                    if cpu_mesh.normals.is_none() {
+                        cpu_mesh.compute_normals();
+                    }
+                    cpu_meshes.push(cpu_mesh);
+                }
+            }
+        }
+        Ok((cpu_meshes, cpu_materials))
+    }
 
-                let mut map: HashMap<usize, usize> = HashMap::new();
+    ///
+    /// Deserialize the lines and points in a loaded .obj file resource into a list of line meshes and a list of point meshes,
+    /// ie. meshes with the [primitive type](crate::PrimitiveType) lines or points which can be rendered using a [Mesh](crate::Mesh).
+    /// The indices of a line mesh come in pairs, each pair defining a line segment, and the indices of a point mesh define one point each.
+    /// Polylines (`l` statements with more than two vertices) are split into line segments.
+    /// The meshes are named after the object they belong to and refer to the materials returned by [obj](Self::obj).
+    ///
+    /// # Feature
+    /// Only available when the `obj-io` feature is enabled.
+    ///
+    pub fn obj_lines_and_points<P: AsRef<Path>>(&self, path: P) -> Result<(Vec<CPUMesh>, Vec<CPUMesh>), IOError> {
+        let obj = self.parse_obj(path.as_ref())?;
+        let mut line_meshes = Vec::new();
+        let mut point_meshes = Vec::new();
+        for object in obj.objects.iter() {
+            for mesh in object.geometry.iter() {
+                let mut lines = Vec::new();
+                let mut points = Vec::new();
+                for shape in mesh.shapes.iter() {
+                    match shape.primitive {
+                        wavefront_obj::obj::Primitive::Line(i0, i1) => lines.push(vec![i0, i1]),
+                        wavefront_obj::obj::Primitive::Point(i0) => points.push(vec![i0]),
+                        _ => {}
+                    }
+                }
+                if !lines.is_empty() {
+                    line_meshes.push(obj_mesh(object, mesh.material_name.clone(), PrimitiveType::Lines, &lines));
+                }
+                if !points.is_empty() {
+                    point_meshes.push(obj_mesh(object, mesh.material_name.clone(), PrimitiveType::Points, &points));
+                }
+            }
+        }
+        Ok((line_meshes, point_meshes))
+    }
 
-                let mut process = |i: wavefront_obj::obj::VTNIndex| {
+    fn parse_obj(&self, path: &Path) -> Result<wavefront_obj::obj::ObjSet, IOError> {
+        let obj_bytes = self.bytes(path)?;
+        let source = String::from_utf8(obj_bytes.to_owned()).map_err(|_|
+            IOError::FailedToLoad {message: format!("The obj file {:?} is not valid UTF-8.", path)})?;
+        Ok(wavefront_obj::obj::parse(triangulate_obj_source(&source))?)
+    }
+}
 
-                    let mut index = map.get(&i.0).map(|v| *v);
+struct MtlMaterial {
+    name: String,
+    ambient: [f32; 3],
+    diffuse: [f32; 3],
+    specular: [f32; 3],
+    emissive: Option<[f32; 3]>,
+    specular_power: f32,
+    alpha: f32,
+    illumination: Option<u32>,
+    diffuse_map: Option<String>,
+    specular_map: Option<String>,
+    bump_map: Option<String>,
+    opacity_map: Option<String>
+}
 
-                    let uvw = i.1.map(|tex_index| object.tex_vertices[tex_index]);
-                    let normal = i.2.map(|normal_index| object.normals[normal_index]);
+// The statements which are used by parse_mtl, except newmtl
+const MTL_STATEMENTS: [&str; 15] = ["Ka", "Kd", "Ks", "Ke", "Ns", "d", "Tr", "illum", "map_Kd", "map_Ks", "map_Bump", "map_bump", "bump", "norm", "map_d"];
 
-                    if let Some(ind) = index {
-                        if let Some(tex) = uvw {
-                            if ((uvs[ind*2] - tex.u as f32) as f32).abs() > std::f32::EPSILON ||
-                                ((uvs[ind*2+1] - tex.v as f32) as f32).abs() > std::f32::EPSILON {
-                                index = None;
-                            }
-                        }
-                        if let Some(n) = normal {
-                            if ((normals[ind*3] - n.x as f32) as f32).abs() > std::f32::EPSILON ||
-                                ((normals[ind*3+1] - n.y as f32) as f32).abs() > std::f32::EPSILON ||
-                                ((normals[ind*3+2] - n.z as f32) as f32).abs() > std::f32::EPSILON {
-                                index = None;
-                            }
-                        }
-                    }
+///
+/// Parses the materials in an .mtl file. Statements can be in any order and unsupported statements are ignored.
+///
+fn parse_mtl(source: &str) -> Result<Vec<MtlMaterial>, IOError> {
+    let mut materials: Vec<MtlMaterial> = Vec::new();
+    for (line_number, line) in source.lines().enumerate() {
+        let line = strip_mtl_comment(line).trim();
+        let mut tokens = line.split_whitespace();
+        let statement = if let Some(statement) = tokens.next() { statement } else { continue };
+        let arguments: Vec<&str> = tokens.collect();
+        let error = || IOError::FailedToLoad {message: format!("Could not parse line {} in mtl file: {}", line_number + 1, line)};
+        let float = |i: usize| -> Result<f32, IOError> { arguments.get(i).and_then(|a| a.parse::<f32>().ok()).ok_or_else(error) };
+        let color = || -> Result<[f32; 3], IOError> {
+            let r = float(0)?;
+            // A single value means a gray color
+            Ok(if arguments.len() >= 3 { [r, float(1)?, float(2)?] } else { [r, r, r] })
+        };
 
-                    if index.is_none() {
-                        index = Some(positions.len() / 3);
-                        map.insert(i.0, index.unwrap());
-                        let position = object.vertices[i.0];
-                        positions.push(position.x as f32);
-                        positions.push(position.y as f32);
-                        positions.push(position.z as f32);
-
-                        if let Some(tex) = uvw {
-                            uvs.push(tex.u as f32);
-                            uvs.push(tex.v as f32);
-                        }
-                        if let Some(n) = normal {
-                            normals.push(n.x as f32);
-                            normals.push(n.y as f32);
-                            normals.push(n.z as f32);
+        if statement == "newmtl" {
+            materials.push(MtlMaterial {
+                name: arguments.join(" "),
+                ambient: [0.0; 3],
+                diffuse: [0.8; 3],
+                specular: [0.0; 3],
+                emissive: None,
+                specular_power: 6.0,
+                alpha: 1.0,
+                illumination: None,
+                diffuse_map: None,
+                specular_map: None,
+                bump_map: None,
+                opacity_map: None
+            });
+            continue;
+        }
+        let material = match materials.last_mut() {
+            Some(material) => material,
+            // Only the supported statements require a material, so unsupported statements before the first material are ignored
+            None if !MTL_STATEMENTS.contains(&statement) => continue,
+            None => Err(error())?
+        };
+        match statement {
+            "Ka" => material.ambient = color()?,
+            "Kd" => material.diffuse = color()?,
+            "Ks" => material.specular = color()?,
+            "Ke" => material.emissive = Some(color()?),
+            "Ns" => material.specular_power = float(0)?,
+            "d" => material.alpha = float(arguments.len().max(1) - 1)?,
+            "Tr" => material.alpha = 1.0 - float(0)?,
+            "illum" => material.illumination = Some(float(0)? as u32),
+            "map_Kd" => material.diffuse_map = mtl_texture_name(&arguments),
+            "map_Ks" => material.specular_map = mtl_texture_name(&arguments),
+            "map_Bump" | "map_bump" | "bump" | "norm" => material.bump_map = mtl_texture_name(&arguments),
+            "map_d" => material.opacity_map = mtl_texture_name(&arguments),
+            _ => {}
+        }
+    }
+    Ok(materials)
+}
+
+///
+/// Removes a comment from a line in an .mtl file. A comment starts with a `#` at the start of the line or after whitespace,
+/// so file names containing `#` are kept.
+///
+fn strip_mtl_comment(line: &str) -> &str {
+    let mut previous = ' ';
+    for (i, c) in line.char_indices() {
+        if c == '#' && previous.is_whitespace() {
+            return &line[..i];
+        }
+        previous = c;
+    }
+    line
+}
+
+///
+/// Returns the texture file name of a texture map statement, skipping the options in front of it (for example `-bm 0.5`).
+///
+fn mtl_texture_name(arguments: &[&str]) -> Option<String> {
+    let mut i = 0;
+    while i < arguments.len() && arguments[i].starts_with('-') && arguments[i].parse::<f32>().is_err() {
+        let option = arguments[i];
+        i += 1;
+        if option == "-imfchan" || option == "-type" {
+            i += 1;
+        } else {
+            while i < arguments.len() && (arguments[i].parse::<f32>().is_ok() || arguments[i] == "on" || arguments[i] == "off") {
+                i += 1;
+            }
+        }
+    }
+    if i < arguments.len() { Some(arguments[i..].join(" ")) } else { None }
+}
+
+fn obj_mesh(object: &wavefront_obj::obj::Object, material_name: Option<String>, primitive_type: PrimitiveType, primitives: &[Vec<wavefront_obj::obj::VTNIndex>]) -> CPUMesh {
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut uvs = Vec::new();
+    let mut indices = Vec::new();
+
+    let mut map: HashMap<usize, usize> = HashMap::new();
+
+    let mut process = |i: wavefront_obj::obj::VTNIndex| {
+
+        let mut index = map.get(&i.0).map(|v| *v);
+
+        let uvw = i.1.map(|tex_index| object.tex_vertices[tex_index]);
+        let normal = i.2.map(|normal_index| object.normals[normal_index]);
+
+        if let Some(ind) = index {
+            if let Some(tex) = uvw {
+                if ((uvs[ind*2] - tex.u as f32) as f32).abs() > std::f32::EPSILON ||
+                    ((uvs[ind*2+1] - tex.v as f32) as f32).abs() > std::f32::EPSILON {
+                    index = None;
+                }
+            }
+            if let Some(n) = normal {
+                if ((normals[ind*3] - n.x as f32) as f32).abs() > std::f32::EPSILON ||
+                    ((normals[ind*3+1] - n.y as f32) as f32).abs() > std::f32::EPSILON ||
+                    ((normals[ind*3+2] - n.z as f32) as f32).abs() > std::f32::EPSILON {
+                    index = None;
+                }
+            }
+        }
+
+        if index.is_none() {
+            index = Some(positions.len() / 3);
+            map.insert(i.0, index.unwrap());
+            let position = object.vertices[i.0];
+            positions.push(position.x as f32);
+            positions.push(position.y as f32);
+            positions.push(position.z as f32);
+
+            if let Some(tex) = uvw {
+                uvs.push(tex.u as f32);
+                uvs.push(tex.v as f32);
+            }
+            if let Some(n) = normal {
+                normals.push(n.x as f32);
+                normals.push(n.y as f32);
+                normals.push(n.z as f32);
+            }
+        }
+
+        indices.push(index.unwrap() as u32);
+    };
+    for primitive in primitives.iter() {
+        for i in primitive.iter() {
+            process(*i);
+        }
+    }
+
+    let vertex_count = positions.len() / 3;
+    CPUMesh {
+        name: object.name.to_string(),
+        material_name,
+        primitive_type,
+        positions,
+        indices: Some(indices),
+        // Only some of the face corners might specify normals and uv coordinates
+        normals: if normals.len() == vertex_count * 3 { Some(normals) } else { None },
+        tangents: None,
+        uvs: if uvs.len() == vertex_count * 2 { Some(uvs) } else { None },
+        colors: None
+    }
+}
+
+///
+/// Rewrites the obj source so that the wavefront_obj parser, which fan triangulates every face and polyline,
+/// produces the correct primitives: faces with more than three corners are ear clipped into triangles,
+/// polylines are split into line segments and point statements are turned into single vertex lines.
+///
+fn triangulate_obj_source(source: &str) -> String {
+    use crate::math::*;
+    // Vertices which cannot be parsed are None, so the indices of the following vertices are still correct
+    let mut vertices: Vec<Option<Vec3>> = Vec::new();
+    let mut result = String::with_capacity(source.len());
+    for line in source.lines() {
+        let mut tokens = line.split_whitespace();
+        match tokens.next() {
+            Some("v") => {
+                let coords: Vec<f32> = tokens.take(3).filter_map(|t| t.parse().ok()).collect();
+                vertices.push(if coords.len() == 3 { Some(vec3(coords[0], coords[1], coords[2])) } else { None });
+            },
+            Some("f") => {
+                let corners: Vec<&str> = tokens.collect();
+                if corners.len() > 3 {
+                    let points: Option<Vec<Vec3>> = corners.iter().map(|corner| {
+                        let index = corner.split('/').next()?.parse::<isize>().ok()?;
+                        let index = if index < 0 { vertices.len() as isize + index } else { index - 1 };
+                        vertices.get(index as usize).copied().flatten()
+                    }).collect();
+                    // Leave the face as it is if the indices or vertices are invalid and let the parser report the error
+                    if let Some(points) = points {
+                        for [a, b, c] in triangulate_polygon(&points) {
+                            result.push_str(&format!("f {} {} {}\n", corners[a], corners[b], corners[c]));
                         }
+                        continue;
                     }
-
-                    indices.push(index.unwrap() as u32);
-                };
-                for shape in mesh.shapes.iter() { // All triangles with same material
-                    match shape.primitive {
-                        wavefront_obj::obj::Primitive::Triangle(i0, i1, i2) => {
-                            process(i0);
-                            process(i1);
-                            process(i2);
-                        },
-                        _ => {}
+                }
+            },
+            Some("l") => {
+                let corners: Vec<&str> = tokens.collect();
+                if corners.len() > 2 {
+                    for segment in corners.windows(2) {
+                        result.push_str(&format!("l {} {}\n", segment[0], segment[1]));
                     }
+                    continue;
                 }
+            },
+            Some("p") => {
+                for corner in tokens {
+                    result.push_str(&format!("l {}\n", corner));
+                }
+                continue;
+            },
+            _ => {}
+        }
+        result.push_str(line);
+        result.push('\n');
+    }
+    result
+}
 
-                cpu_meshes.push(CPUMesh {
-                    name: object.name.to_string(),
-                    material_name: mesh.material_name.clone(),
-                    positions,
-                    indices: Some(indices),
-                    normals: Some(normals),
-                    uvs: Some(uvs),
-                    colors: None
-                });
+///
+/// Triangulates a planar polygon, which can be non-convex, using ear clipping.
+/// Returns the corner indices of the triangles with the same winding as the polygon.
+///
+fn triangulate_polygon(points: &[crate::math::Vec3]) -> Vec<[usize; 3]> {
+    use crate::math::*;
+    // Project the polygon onto the plane where it has the largest area
+    let mut normal = vec3(0.0, 0.0, 0.0);
+    for i in 0..points.len() {
+        let (p0, p1) = (points[i], points[(i + 1) % points.len()]);
+        normal += vec3((p0.y - p1.y) * (p0.z + p1.z), (p0.z - p1.z) * (p0.x + p1.x), (p0.x - p1.x) * (p0.y + p1.y));
+    }
+    let (abs_x, abs_y, abs_z) = (normal.x.abs(), normal.y.abs(), normal.z.abs());
+    let project = |p: Vec3| -> Vec2 {
+        if abs_x >= abs_y && abs_x >= abs_z {
+            if normal.x > 0.0 { vec2(p.y, p.z) } else { vec2(p.z, p.y) }
+        } else if abs_y >= abs_z {
+            if normal.y > 0.0 { vec2(p.z, p.x) } else { vec2(p.x, p.z) }
+        } else if normal.z > 0.0 { vec2(p.x, p.y) } else { vec2(p.y, p.x) }
+    };
+    let points: Vec<Vec2> = points.iter().map(|p| project(*p)).collect();
+    let cross = |a: Vec2, b: Vec2, c: Vec2| (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x);
+
+    let mut remaining: Vec<usize> = (0..points.len()).collect();
+    let mut triangles = Vec::with_capacity(points.len() - 2);
+    while remaining.len() > 3 {
+        let n = remaining.len();
+        let ear = (0..n).find(|&i| {
+            let (a, b, c) = (remaining[(i + n - 1) % n], remaining[i], remaining[(i + 1) % n]);
+            if cross(points[a], points[b], points[c]) <= 0.0 {
+                return false; // Reflex or degenerate corner
+            }
+            !remaining.iter().any(|&j| j != a && j != b && j != c &&
+                cross(points[a], points[b], points[j]) >= 0.0 &&
+                cross(points[b], points[c], points[j]) >= 0.0 &&
+                cross(points[c], points[a], points[j]) >= 0.0)
+        }).unwrap_or(0); // Self-intersecting or degenerate polygon, just clip the first corner
+        triangles.push([remaining[(ear + n - 1) % n], remaining[ear], remaining[(ear + 1) % n]]);
+        remaining.remove(ear);
+    }
+    triangles.push([remaining[0], remaining[1], remaining[2]]);
+    triangles
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Saver {
+    ///
+    /// Saves the given meshes and materials as an .obj file and an .mtl material file.
+    /// The texture images of the materials are saved as .png files next to the .obj file.
+    /// Whitespace, path separators and comment characters in the mesh and material names are replaced by underscores.
+    ///
+    /// # Feature
+    /// Only available when the `obj-io` feature is enabled.
+    ///
+    pub fn save_obj<P: AsRef<Path>>(path: P, cpu_meshes: &[CPUMesh], cpu_materials: &[CPUMaterial]) -> Result<(), IOError>
+    {
+        let dir = path.as_ref().parent().unwrap_or(Path::new("./"));
+        let filename = path.as_ref().file_stem().map(|stem| stem.to_string_lossy().to_string())
+            .ok_or_else(|| IOError::FailedToSave {message: format!("The path {} does not have a file name.", path.as_ref().display())})?;
+
+        let mut mtl = String::new();
+        for cpu_material in cpu_materials.iter() {
+            let color = cpu_material.color.unwrap_or((1.0, 1.0, 1.0, 1.0));
+            let specular = cpu_material.specular_intensity.unwrap_or(0.2);
+            let material_name = obj_name(&cpu_material.name);
+            mtl.push_str(&format!("newmtl {}\n", material_name));
+            mtl.push_str(&format!("Ns {}\n", cpu_material.specular_power.unwrap_or(6.0)));
+            mtl.push_str("Ka 0 0 0\n");
+            mtl.push_str(&format!("Kd {} {} {}\n", color.0, color.1, color.2));
+            mtl.push_str(&format!("Ks {} {} {}\n", specular, specular, specular));
+            mtl.push_str(&format!("d {}\n", color.3));
+            if let Some(emissive) = cpu_material.emissive_color {
+                mtl.push_str(&format!("Ke {} {} {}\n", emissive.0, emissive.1, emissive.2));
+            }
+            mtl.push_str("illum 2\n");
+            let textures = [("map_Kd", "", &cpu_material.texture_image), ("map_Ks", "_specular", &cpu_material.specular_image),
+                ("map_Bump", "_bump", &cpu_material.bump_image), ("map_d", "_opacity", &cpu_material.opacity_image)];
+            for (statement, suffix, cpu_texture) in textures.iter() {
+                if let Some(cpu_texture) = cpu_texture {
+                    let texture_name = format!("{}_{}{}.png", obj_name(&filename), material_name, suffix);
+                    Self::save_file(dir.join(&texture_name), &png_from_texture(cpu_texture)?)?;
+                    mtl.push_str(&format!("{} {}\n", statement, texture_name));
+                }
             }
+            mtl.push('\n');
         }
-        Ok((cpu_meshes, cpu_materials))
+
+        let mut obj = String::new();
+        if !cpu_materials.is_empty() {
+            let material_library = format!("{}.mtl", obj_name(&filename));
+            obj.push_str(&format!("mtllib {}\n", material_library));
+            Self::save_file(dir.join(material_library), mtl.as_bytes())?;
+        }
+
+        let mut position_offset = 1;
+        let mut uv_offset = 1;
+        let mut normal_offset = 1;
+        for cpu_mesh in cpu_meshes.iter() {
+            let vertex_count = cpu_mesh.positions.len() / 3;
+            obj.push_str(&format!("o {}\n", obj_name(&cpu_mesh.name)));
+            for p in cpu_mesh.positions.chunks(3) {
+                obj.push_str(&format!("v {} {} {}\n", p[0], p[1], p[2]));
+            }
+            if let Some(ref uvs) = cpu_mesh.uvs {
+                for uv in uvs.chunks(2) {
+                    obj.push_str(&format!("vt {} {}\n", uv[0], uv[1]));
+                }
+            }
+            if let Some(ref normals) = cpu_mesh.normals {
+                for n in normals.chunks(3) {
+                    obj.push_str(&format!("vn {} {} {}\n", n[0], n[1], n[2]));
+                }
+            }
+            if let Some(ref material_name) = cpu_mesh.material_name {
+                obj.push_str(&format!("usemtl {}\n", obj_name(material_name)));
+            }
+
+            let indices = cpu_mesh.indices.clone().unwrap_or((0..vertex_count as u32).collect());
+            let has_uvs = cpu_mesh.uvs.as_ref().map(|uvs| !uvs.is_empty()).unwrap_or(false);
+            let has_normals = cpu_mesh.normals.as_ref().map(|normals| !normals.is_empty()).unwrap_or(false);
+            let vertex = |i: u32| {
+                let i = i as usize;
+                match (has_uvs, has_normals) {
+                    (true, true) => format!("{}/{}/{}", position_offset + i, uv_offset + i, normal_offset + i),
+                    (true, false) => format!("{}/{}", position_offset + i, uv_offset + i),
+                    (false, true) => format!("{}//{}", position_offset + i, normal_offset + i),
+                    (false, false) => format!("{}", position_offset + i)
+                }
+            };
+            match cpu_mesh.primitive_type {
+                PrimitiveType::Triangles => for face in indices.chunks_exact(3) {
+                    obj.push_str(&format!("f {} {} {}\n", vertex(face[0]), vertex(face[1]), vertex(face[2])));
+                },
+                // Lines and points cannot refer to normals
+                PrimitiveType::Lines => for line in indices.chunks_exact(2) {
+                    let vertex = |i: u32| if has_uvs { format!("{}/{}", position_offset + i as usize, uv_offset + i as usize) } else { format!("{}", position_offset + i as usize) };
+                    obj.push_str(&format!("l {} {}\n", vertex(line[0]), vertex(line[1])));
+                },
+                PrimitiveType::Points => for point in indices.iter() {
+                    obj.push_str(&format!("p {}\n", position_offset + *point as usize));
+                }
+            }
+
+            position_offset += vertex_count;
+            uv_offset += cpu_mesh.uvs.as_ref().map(|uvs| uvs.len() / 2).unwrap_or(0);
+            normal_offset += cpu_mesh.normals.as_ref().map(|normals| normals.len() / 3).unwrap_or(0);
+        }
+        Self::save_file(dir.join(format!("{}.obj", filename)), obj.as_bytes())?;
+        Ok(())
+    }
+}
+
+// A name can only be a single word in .obj and .mtl files, is used in the texture file names and '#' starts a comment
+#[cfg(not(target_arch = "wasm32"))]
+fn obj_name(name: &str) -> String {
+    name.replace(|c: char| c.is_whitespace() || c == '/' || c == '\\' || c == '#', "_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::*;
+
+    fn load(source: &str) -> Loaded {
+        let mut loaded = HashMap::new();
+        loaded.insert(std::path::PathBuf::from("model.obj"), Ok(source.as_bytes().to_vec()));
+        Loaded::new(loaded)
+    }
+
+    // An arrow shaped pentagon where the corner at (1, 1) is concave
+    const CONCAVE_PENTAGON: &str = "o arrow
+v 0 0 0
+v 2 0 0
+v 2 2 0
+v 1 1 0
+v 0 2 0
+f 1 2 3 4 5
+";
+
+    fn signed_area(a: Vec3, b: Vec3, c: Vec3) -> f32 {
+        0.5 * (b - a).cross(c - a).z
+    }
+
+    #[test]
+    fn triangulate_concave_polygon() {
+        let points = [vec3(0.0, 0.0, 0.0), vec3(2.0, 0.0, 0.0), vec3(2.0, 2.0, 0.0), vec3(1.0, 1.0, 0.0), vec3(0.0, 2.0, 0.0)];
+        let triangles = triangulate_polygon(&points);
+        assert_eq!(triangles.len(), 3);
+        // All triangles have the winding of the polygon and together they cover exactly the area of the polygon
+        let areas: Vec<f32> = triangles.iter().map(|[a, b, c]| signed_area(points[*a], points[*b], points[*c])).collect();
+        assert!(areas.iter().all(|area| *area > 0.0));
+        assert!((areas.iter().sum::<f32>() - 3.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn load_concave_face() {
+        let (cpu_meshes, _) = load(CONCAVE_PENTAGON).obj("model.obj").unwrap();
+        assert_eq!(cpu_meshes.len(), 1);
+        let cpu_mesh = &cpu_meshes[0];
+        let indices = cpu_mesh.indices.as_ref().unwrap();
+        assert_eq!(indices.len(), 9);
+        let position = |i: u32| vec3(cpu_mesh.positions[i as usize * 3], cpu_mesh.positions[i as usize * 3 + 1], cpu_mesh.positions[i as usize * 3 + 2]);
+        let area: f32 = indices.chunks(3).map(|t| signed_area(position(t[0]), position(t[1]), position(t[2]))).sum();
+        assert!((area - 3.0).abs() < 1e-6);
+        assert!(cpu_mesh.normals.as_ref().unwrap().chunks(3).all(|n| n == [0.0, 0.0, 1.0]));
+    }
+
+    #[test]
+    fn triangulate_with_invalid_vertex() {
+        // The invalid vertex still counts, so the face refers to the last four vertices and is triangulated
+        let source = "v 0 0 0\nv a b c\nv 0 0 0\nv 2 0 0\nv 2 2 0\nv 0 2 0\nf 3 4 5 6\n";
+        assert!(triangulate_obj_source(source).ends_with("f 6 3 4\nf 4 5 6\n"));
+        // Faces which refer to an invalid vertex are left as they are
+        let source = "v 0 0 0\nv a b c\nv 2 2 0\nv 0 2 0\nf 1 2 3 4\n";
+        assert!(triangulate_obj_source(source).ends_with("f 1 2 3 4\n"));
+    }
+
+    #[test]
+    fn load_lines_and_points() {
+        let source = "o lines
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+l 1 2 3 4
+p 1 3
+";
+        let (line_meshes, point_meshes) = load(source).obj_lines_and_points("model.obj").unwrap();
+        assert_eq!(line_meshes.len(), 1);
+        assert_eq!(line_meshes[0].name, "lines");
+        // The polyline is split into three line segments
+        assert_eq!(line_meshes[0].indices, Some(vec![0, 1, 1, 2, 2, 3]));
+        assert_eq!(line_meshes[0].positions, vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 1.0, 0.0]);
+        assert_eq!(point_meshes.len(), 1);
+        assert_eq!(point_meshes[0].indices, Some(vec![0, 1]));
+        assert_eq!(point_meshes[0].positions, vec![0.0, 0.0, 0.0, 1.0, 1.0, 0.0]);
+        assert_eq!(line_meshes[0].primitive_type, PrimitiveType::Lines);
+        assert_eq!(point_meshes[0].primitive_type, PrimitiveType::Points);
+        assert!(line_meshes[0].validate().is_valid() && point_meshes[0].validate().is_valid());
+        // Lines and points are not part of the triangle meshes
+        assert!(load(source).obj("model.obj").unwrap().0.is_empty());
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn save_and_load() {
+        let dir = std::env::temp_dir().join(format!("three-d-save-obj-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let cpu_mesh = CPUMesh {
+            name: "my square".to_string(),
+            material_name: Some("red #1\nshiny".to_string()),
+            positions: vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 1.0, 0.0],
+            normals: Some(vec![0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0]),
+            uvs: Some(vec![0.0, 0.0, 1.0, 0.0, 1.0, 1.0, 0.0, 1.0]),
+            indices: Some(vec![0, 1, 2, 0, 2, 3]),
+            ..Default::default()
+        };
+        let cpu_material = CPUMaterial {
+            name: "red #1\nshiny".to_string(),
+            color: Some((1.0, 0.0, 0.0, 1.0)),
+            texture_image: Some(Rc::new(CPUTexture {data: vec![255, 0, 0], width: 1, height: 1, format: Format::RGB8, ..Default::default()})),
+            ..Default::default()
+        };
+        Saver::save_obj(dir.join("my model.obj"), std::slice::from_ref(&cpu_mesh), &[cpu_material]).unwrap();
+
+        let mut loaded = HashMap::new();
+        for entry in std::fs::read_dir(&dir).unwrap() {
+            let path = entry.unwrap().path();
+            loaded.insert(path.clone(), Ok(std::fs::read(&path).unwrap()));
+        }
+        let (cpu_meshes, cpu_materials) = Loaded::new(loaded).obj(dir.join("my model.obj")).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(cpu_meshes.len(), 1);
+        assert_eq!(cpu_meshes[0].name, "my_square");
+        assert_eq!(cpu_meshes[0].material_name.as_deref(), Some("red__1_shiny"));
+        // The triangles are the same, but may start at another corner
+        let triangles = |cpu_mesh: &CPUMesh| cpu_mesh.indices.as_ref().unwrap().chunks(3).map(|triangle| {
+            let mut corners: Vec<Vec<f32>> = triangle.iter().map(|i| {
+                let i = *i as usize;
+                [&cpu_mesh.positions[i * 3..i * 3 + 3], &cpu_mesh.uvs.as_ref().unwrap()[i * 2..i * 2 + 2]].concat()
+            }).collect();
+            let first = (0..3).min_by(|a, b| corners[*a].partial_cmp(&corners[*b]).unwrap()).unwrap();
+            corners.rotate_left(first);
+            corners
+        }).collect::<Vec<_>>();
+        assert_eq!(triangles(&cpu_meshes[0]), triangles(&cpu_mesh));
+        assert_eq!(cpu_materials.len(), 1);
+        assert_eq!(cpu_materials[0].name, "red__1_shiny");
+        assert_eq!(cpu_materials[0].color, Some((1.0, 0.0, 0.0, 1.0)));
+        assert_eq!(cpu_materials[0].texture_image.as_ref().unwrap().data, vec![255, 0, 0]);
+    }
+
+    #[test]
+    fn parse_mtl_statements() {
+        let source = "# Exported material
+Ni 1.0
+newmtl first # a comment
+Kd 1 0 0
+Ke 0.5
+d 0.5
+map_Kd -bm 0.5 textures/color#1.png # the texture
+map_Bump normal.png
+
+newmtl second
+Ks 0.1 0.2 0.3
+Tr 0.25
+unknown statement
+";
+        let materials = parse_mtl(source).unwrap();
+        assert_eq!(materials.len(), 2);
+        assert_eq!(materials[0].name, "first");
+        assert_eq!(materials[0].diffuse, [1.0, 0.0, 0.0]);
+        assert_eq!(materials[0].emissive, Some([0.5, 0.5, 0.5]));
+        assert_eq!(materials[0].alpha, 0.5);
+        assert_eq!(materials[0].diffuse_map.as_deref(), Some("textures/color#1.png"));
+        assert_eq!(materials[0].bump_map.as_deref(), Some("normal.png"));
+        assert_eq!(materials[1].name, "second");
+        assert_eq!(materials[1].specular, [0.1, 0.2, 0.3]);
+        assert_eq!(materials[1].alpha, 0.75);
+        assert!(materials[1].diffuse_map.is_none());
+
+        // A supported statement requires a material
+        assert!(parse_mtl("Kd 1 0 0\nnewmtl first\n").is_err());
+        assert!(parse_mtl("Ni 1.0\nnewmtl first\n").is_ok());
+        assert!(parse_mtl("newmtl first\nKd 1 zero 0\n").is_err());
     }
-}
\ No newline at end of file
+}
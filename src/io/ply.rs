@@ -0,0 +1,439 @@
+use crate::io::*;
+use std::path::Path;
+use crate::definition::*;
+
+impl Loaded {
+    ///
+    /// Deserialize a loaded ASCII or binary (little or big endian) .ply file resource into a mesh.
+    /// Supports vertex positions, normals, uv coordinates and colors as well as faces which are triangulated.
+    /// If the file does not contain any faces, ie. it is a point cloud, the indices of the returned mesh is `None`.
+    ///
+    /// # Feature
+    /// Only available when the `ply-io` feature is enabled.
+    ///
+    pub fn ply<P: AsRef<Path>>(&self, path: P) -> Result<CPUMesh, IOError> {
+        let bytes = self.bytes(path.as_ref())?;
+        let (header, body) = parse_ply_header(bytes)?;
+        let mut reader = PlyReader {format: header.format, bytes: body, offset: 0};
+
+        let mut positions = Vec::new();
+        let mut normals = Vec::new();
+        let mut uvs = Vec::new();
+        let mut colors = Vec::new();
+        let mut indices = Vec::new();
+        let mut has_faces = false;
+        for element in header.elements.iter() {
+            let is_vertex = element.name == "vertex";
+            let is_face = element.name == "face";
+            has_faces |= is_face && element.count > 0;
+            let find = |names: &[&str]| element.properties.iter().position(|p| names.contains(&p.name.as_str()));
+            let position_index = [find(&["x"]), find(&["y"]), find(&["z"])];
+            let normal_index = [find(&["nx"]), find(&["ny"]), find(&["nz"])];
+            let uv_index = [find(&["u", "s", "texture_u", "texture_s"]), find(&["v", "t", "texture_v", "texture_t"])];
+            let color_index = [find(&["red", "r", "diffuse_red"]), find(&["green", "g", "diffuse_green"]),
+                find(&["blue", "b", "diffuse_blue"]), find(&["alpha", "a"])];
+            let face_index = find(&["vertex_indices", "vertex_index"]);
+
+            for _ in 0..element.count {
+                let mut values = Vec::with_capacity(element.properties.len());
+                for property in element.properties.iter() {
+                    values.push(reader.read_property(property)?);
+                }
+                let scalar = |i: Option<usize>| i.and_then(|i| values[i].first().cloned());
+                if is_vertex {
+                    for i in position_index.iter() {
+                        positions.push(scalar(*i).ok_or(
+                            IOError::FailedToLoad {message: "The PLY vertex element must have x, y and z properties.".to_string()})? as f32);
+                    }
+                    if normal_index.iter().all(|i| i.is_some()) {
+                        normals.extend(normal_index.iter().map(|i| scalar(*i).unwrap() as f32));
+                    }
+                    if uv_index.iter().all(|i| i.is_some()) {
+                        uvs.extend(uv_index.iter().map(|i| scalar(*i).unwrap() as f32));
+                    }
+                    if color_index[..3].iter().all(|i| i.is_some()) {
+                        for (channel, i) in color_index.iter().enumerate() {
+                            colors.push(match (i, scalar(*i)) {
+                                (Some(i), Some(value)) => if element.properties[*i].data_type.is_float() {
+                                    (value * 255.0).clamp(0.0, 255.0) as u8
+                                } else {
+                                    value as u8
+                                },
+                                _ => if channel == 3 { 255 } else { 0 }
+                            });
+                        }
+                    }
+                } else if is_face {
+                    if let Some(i) = face_index {
+                        let face = &values[i];
+                        for j in 1..face.len().max(1) - 1 {
+                            indices.push(face[0] as u32);
+                            indices.push(face[j] as u32);
+                            indices.push(face[j + 1] as u32);
+                        }
+                    }
+                }
+            }
+        }
+
+        let vertex_count = positions.len() / 3;
+        Ok(CPUMesh {
+            name: path.as_ref().file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default(),
+            positions,
+            indices: if has_faces { Some(indices) } else { None },
+            normals: if normals.len() == vertex_count * 3 && vertex_count > 0 { Some(normals) } else { None },
+            uvs: if uvs.len() == vertex_count * 2 && vertex_count > 0 { Some(uvs) } else { None },
+            colors: if colors.len() == vertex_count * 4 && vertex_count > 0 { Some(colors) } else { None },
+            ..Default::default()
+        })
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+enum PlyFormat {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+enum PlyDataType {
+    Int8,
+    UInt8,
+    Int16,
+    UInt16,
+    Int32,
+    UInt32,
+    Float32,
+    Float64
+}
+
+impl PlyDataType {
+    fn parse(name: &str) -> Result<Self, IOError> {
+        Ok(match name {
+            "char" | "int8" => PlyDataType::Int8,
+            "uchar" | "uint8" => PlyDataType::UInt8,
+            "short" | "int16" => PlyDataType::Int16,
+            "ushort" | "uint16" => PlyDataType::UInt16,
+            "int" | "int32" => PlyDataType::Int32,
+            "uint" | "uint32" => PlyDataType::UInt32,
+            "float" | "float32" => PlyDataType::Float32,
+            "double" | "float64" => PlyDataType::Float64,
+            _ => Err(IOError::FailedToLoad {message: format!("Unknown PLY data type '{}'.", name)})?
+        })
+    }
+
+    fn size(&self) -> usize {
+        match self {
+            PlyDataType::Int8 | PlyDataType::UInt8 => 1,
+            PlyDataType::Int16 | PlyDataType::UInt16 => 2,
+            PlyDataType::Int32 | PlyDataType::UInt32 | PlyDataType::Float32 => 4,
+            PlyDataType::Float64 => 8
+        }
+    }
+
+    fn is_float(&self) -> bool {
+        *self == PlyDataType::Float32 || *self == PlyDataType::Float64
+    }
+}
+
+struct PlyProperty {
+    name: String,
+    data_type: PlyDataType,
+    list_count_type: Option<PlyDataType>
+}
+
+struct PlyElement {
+    name: String,
+    count: usize,
+    properties: Vec<PlyProperty>
+}
+
+struct PlyHeader {
+    format: PlyFormat,
+    elements: Vec<PlyElement>
+}
+
+fn parse_ply_header(bytes: &[u8]) -> Result<(PlyHeader, &[u8]), IOError> {
+    let end = b"end_header";
+    let end_position = bytes.windows(end.len()).position(|w| w == end).ok_or(
+        IOError::FailedToLoad {message: "The PLY file does not contain an end_header line.".to_string()})?;
+    let mut body_start = end_position + end.len();
+    while body_start < bytes.len() && bytes[body_start] != b'\n' {
+        body_start += 1;
+    }
+    let text = String::from_utf8_lossy(&bytes[..end_position]);
+    let mut lines = text.lines();
+    if lines.next().map(|l| l.trim()) != Some("ply") {
+        Err(IOError::FailedToLoad {message: "The file is not a PLY file.".to_string()})?;
+    }
+
+    let mut format = None;
+    let mut elements: Vec<PlyElement> = Vec::new();
+    for line in lines {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            ["format", f, ..] => format = Some(match *f {
+                "ascii" => PlyFormat::Ascii,
+                "binary_little_endian" => PlyFormat::BinaryLittleEndian,
+                "binary_big_endian" => PlyFormat::BinaryBigEndian,
+                _ => Err(IOError::FailedToLoad {message: format!("Unknown PLY format '{}'.", f)})?
+            }),
+            ["element", name, count] => elements.push(PlyElement {
+                name: name.to_string(),
+                count: count.parse().map_err(|_| IOError::FailedToLoad {message: format!("Invalid PLY element count '{}'.", count)})?,
+                properties: Vec::new()
+            }),
+            ["property", "list", count_type, data_type, name] => elements.last_mut().ok_or(
+                IOError::FailedToLoad {message: "PLY property defined before any element.".to_string()})?
+                .properties.push(PlyProperty {name: name.to_string(), data_type: PlyDataType::parse(data_type)?,
+                list_count_type: Some(PlyDataType::parse(count_type)?)}),
+            ["property", data_type, name] => elements.last_mut().ok_or(
+                IOError::FailedToLoad {message: "PLY property defined before any element.".to_string()})?
+                .properties.push(PlyProperty {name: name.to_string(), data_type: PlyDataType::parse(data_type)?, list_count_type: None}),
+            _ => {} // Comments, obj_info etc.
+        }
+    }
+    let format = format.ok_or(IOError::FailedToLoad {message: "The PLY file does not specify a format.".to_string()})?;
+    Ok((PlyHeader {format, elements}, &bytes[(body_start + 1).min(bytes.len())..]))
+}
+
+struct PlyReader<'a> {
+    format: PlyFormat,
+    bytes: &'a [u8],
+    offset: usize
+}
+
+impl<'a> PlyReader<'a> {
+    fn read_property(&mut self, property: &PlyProperty) -> Result<Vec<f64>, IOError> {
+        if let Some(count_type) = property.list_count_type {
+            let count = self.read_value(count_type)? as usize;
+            (0..count).map(|_| self.read_value(property.data_type)).collect()
+        } else {
+            Ok(vec![self.read_value(property.data_type)?])
+        }
+    }
+
+    fn read_value(&mut self, data_type: PlyDataType) -> Result<f64, IOError> {
+        if self.format == PlyFormat::Ascii {
+            while self.offset < self.bytes.len() && self.bytes[self.offset].is_ascii_whitespace() {
+                self.offset += 1;
+            }
+            let start = self.offset;
+            while self.offset < self.bytes.len() && !self.bytes[self.offset].is_ascii_whitespace() {
+                self.offset += 1;
+            }
+            let token = String::from_utf8_lossy(&self.bytes[start..self.offset]);
+            return token.parse::<f64>().map_err(|_| IOError::FailedToLoad {message: format!("Could not parse the value '{}' in PLY file.", token)});
+        }
+
+        let size = data_type.size();
+        if self.offset + size > self.bytes.len() {
+            Err(IOError::FailedToLoad {message: "Unexpected end of binary PLY file.".to_string()})?;
+        }
+        let mut b = [0u8; 8];
+        b[..size].copy_from_slice(&self.bytes[self.offset..self.offset + size]);
+        if self.format == PlyFormat::BinaryBigEndian {
+            b[..size].reverse();
+        }
+        self.offset += size;
+        Ok(match data_type {
+            PlyDataType::Int8 => b[0] as i8 as f64,
+            PlyDataType::UInt8 => b[0] as f64,
+            PlyDataType::Int16 => i16::from_le_bytes([b[0], b[1]]) as f64,
+            PlyDataType::UInt16 => u16::from_le_bytes([b[0], b[1]]) as f64,
+            PlyDataType::Int32 => i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+            PlyDataType::UInt32 => u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+            PlyDataType::Float32 => f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+            PlyDataType::Float64 => f64::from_le_bytes(b)
+        })
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Saver {
+    ///
+    /// Saves the given mesh as a binary little endian .ply file including the normals, uv coordinates and colors if present.
+    /// If the mesh does not have any indices or consists of points, it is saved as a point cloud, ie. without faces.
+    ///
+    /// # Errors
+    /// Will return an error if the mesh consists of lines.
+    ///
+    /// # Feature
+    /// Only available when the `ply-io` feature is enabled.
+    ///
+    pub fn save_ply<P: AsRef<Path>>(path: P, cpu_mesh: &CPUMesh) -> Result<(), IOError>
+    {
+        let vertex_count = cpu_mesh.positions.len() / 3;
+        let indices = match cpu_mesh.primitive_type {
+            PrimitiveType::Triangles => &cpu_mesh.indices,
+            PrimitiveType::Points => &None,
+            PrimitiveType::Lines => Err(IOError::FailedToSave {message: format!("The mesh {} consists of lines which cannot be saved as a PLY file.", cpu_mesh.name)})?
+        };
+        if !cpu_mesh.is_valid() {
+            Err(IOError::FailedToSave {message: format!("Could not save a PLY file since {}", cpu_mesh.validate())})?;
+        }
+
+        let mut header = format!("ply\nformat binary_little_endian 1.0\ncomment saved by three-d\nelement vertex {}\nproperty float x\nproperty float y\nproperty float z\n", vertex_count);
+        if cpu_mesh.normals.is_some() {
+            header.push_str("property float nx\nproperty float ny\nproperty float nz\n");
+        }
+        if cpu_mesh.uvs.is_some() {
+            header.push_str("property float s\nproperty float t\n");
+        }
+        if cpu_mesh.colors.is_some() {
+            header.push_str("property uchar red\nproperty uchar green\nproperty uchar blue\nproperty uchar alpha\n");
+        }
+        if let Some(indices) = indices {
+            header.push_str(&format!("element face {}\nproperty list uchar uint vertex_indices\n", indices.len() / 3));
+        }
+        header.push_str("end_header\n");
+
+        let mut bytes = header.into_bytes();
+        for i in 0..vertex_count {
+            for value in cpu_mesh.positions[i*3..i*3+3].iter()
+                .chain(cpu_mesh.normals.as_ref().map(|n| &n[i*3..i*3+3]).unwrap_or(&[]))
+                .chain(cpu_mesh.uvs.as_ref().map(|uv| &uv[i*2..i*2+2]).unwrap_or(&[])) {
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
+            if let Some(ref colors) = cpu_mesh.colors {
+                bytes.extend_from_slice(&colors[i*4..i*4+4]);
+            }
+        }
+        if let Some(indices) = indices {
+            for face in indices.chunks(3) {
+                bytes.push(face.len() as u8);
+                for index in face {
+                    bytes.extend_from_slice(&index.to_le_bytes());
+                }
+            }
+        }
+        Self::save_file(path, &bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn triangle() -> CPUMesh {
+        CPUMesh {
+            name: "model".to_string(),
+            positions: vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.5],
+            indices: Some(vec![0, 1, 2]),
+            normals: Some(vec![0.0, 0.0, 1.0, 0.0, 1.0, 0.0, 1.0, 0.0, 0.0]),
+            uvs: Some(vec![0.0, 0.0, 1.0, 0.0, 0.0, 1.0]),
+            colors: Some(vec![255, 0, 0, 255, 0, 255, 0, 128, 0, 0, 255, 0]),
+            ..Default::default()
+        }
+    }
+
+    fn point_cloud() -> CPUMesh {
+        CPUMesh {
+            name: "model".to_string(),
+            positions: vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.5],
+            ..Default::default()
+        }
+    }
+
+    // Writes the mesh in the given format with a quad face, to also test the triangulation of faces
+    fn ply_bytes(format: PlyFormat, cpu_mesh: &CPUMesh) -> Vec<u8> {
+        let vertex_count = cpu_mesh.positions.len() / 3;
+        let format_name = match format {
+            PlyFormat::Ascii => "ascii",
+            PlyFormat::BinaryLittleEndian => "binary_little_endian",
+            PlyFormat::BinaryBigEndian => "binary_big_endian"
+        };
+        let mut header = format!("ply\nformat {} 1.0\nelement vertex {}\nproperty float x\nproperty float y\nproperty float z\n", format_name, vertex_count);
+        if cpu_mesh.indices.is_some() {
+            header.push_str("element face 1\nproperty list uchar int vertex_indices\n");
+        }
+        header.push_str("end_header\n");
+        let mut bytes = header.into_bytes();
+        let faces: Vec<u32> = cpu_mesh.indices.as_ref().map(|_| vec![4, 0, 1, 2, 3]).unwrap_or_default();
+        match format {
+            PlyFormat::Ascii => {
+                for p in cpu_mesh.positions.chunks(3) {
+                    bytes.extend_from_slice(format!("{} {} {}\n", p[0], p[1], p[2]).as_bytes());
+                }
+                if !faces.is_empty() {
+                    bytes.extend_from_slice(format!("{}\n", faces.iter().map(|i| i.to_string()).collect::<Vec<_>>().join(" ")).as_bytes());
+                }
+            },
+            _ => {
+                let big_endian = format == PlyFormat::BinaryBigEndian;
+                for value in cpu_mesh.positions.iter() {
+                    bytes.extend_from_slice(&if big_endian { value.to_be_bytes() } else { value.to_le_bytes() });
+                }
+                if let Some((count, face)) = faces.split_first() {
+                    bytes.push(*count as u8);
+                    for index in face {
+                        bytes.extend_from_slice(&if big_endian { (*index as i32).to_be_bytes() } else { (*index as i32).to_le_bytes() });
+                    }
+                }
+            }
+        }
+        bytes
+    }
+
+    fn load(bytes: Vec<u8>) -> CPUMesh {
+        let mut loaded = HashMap::new();
+        loaded.insert(std::path::PathBuf::from("model.ply"), Ok(bytes));
+        Loaded::new(loaded).ply("model.ply").unwrap()
+    }
+
+    fn check(cpu_mesh: &CPUMesh, expected: &CPUMesh) {
+        assert_eq!(cpu_mesh.name, expected.name);
+        assert_eq!(cpu_mesh.positions, expected.positions);
+        assert_eq!(cpu_mesh.indices, expected.indices);
+        assert_eq!(cpu_mesh.normals, expected.normals);
+        assert_eq!(cpu_mesh.uvs, expected.uvs);
+        assert_eq!(cpu_mesh.colors, expected.colors);
+    }
+
+    #[test]
+    fn load_all_formats() {
+        let quad = CPUMesh {
+            name: "model".to_string(),
+            positions: vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 1.0, 0.0],
+            indices: Some(vec![0, 1, 2, 0, 2, 3]),
+            ..Default::default()
+        };
+        for format in [PlyFormat::Ascii, PlyFormat::BinaryLittleEndian, PlyFormat::BinaryBigEndian].iter() {
+            check(&load(ply_bytes(*format, &quad)), &quad);
+            check(&load(ply_bytes(*format, &point_cloud())), &point_cloud());
+        }
+    }
+
+    #[test]
+    fn save_and_load() {
+        let dir = std::env::temp_dir().join(format!("three-d-ply-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("model.ply");
+        for cpu_mesh in [triangle(), point_cloud()].iter() {
+            Saver::save_ply(&path, cpu_mesh).unwrap();
+            check(&load(std::fs::read(&path).unwrap()), cpu_mesh);
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn save_invalid() {
+        let dir = std::env::temp_dir().join(format!("three-d-ply-invalid-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("model.ply");
+        let invalid_meshes = [
+            CPUMesh {colors: Some(vec![255; 8]), ..triangle()},
+            CPUMesh {normals: Some(vec![0.0; 6]), ..triangle()},
+            CPUMesh {uvs: Some(vec![0.0; 8]), ..triangle()},
+            CPUMesh {indices: Some(vec![0, 1, 3]), ..triangle()},
+            CPUMesh {positions: vec![0.0; 8], ..point_cloud()}
+        ];
+        for cpu_mesh in invalid_meshes.iter() {
+            assert!(Saver::save_ply(&path, cpu_mesh).is_err());
+        }
+        assert!(!path.exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -13,9 +13,7 @@ impl Saver {
     ///
     pub fn save_file<P: AsRef<Path>>(path: P, bytes: &[u8]) -> Result<(), IOError>
     {
-        let mut file = std::fs::File::create(path)?;
-        use std::io::prelude::*;
-        file.write_all(bytes)?;
-        Ok(())
+        std::fs::write(path.as_ref(), bytes)
+            .map_err(|source| IOError::FailedToWriteFile {path: path.as_ref().to_path_buf(), source})
     }
 }
@@ -0,0 +1,272 @@
+use crate::io::*;
+use std::collections::HashMap;
+use std::path::Path;
+use crate::definition::*;
+
+impl Loaded {
+    ///
+    /// Deserialize a loaded binary or ASCII .stl file resource into a mesh.
+    /// Vertices with the same position are welded together and the normals are computed from the welded mesh.
+    /// If an ASCII file contains several solids, they are all added to the same mesh.
+    ///
+    /// # Feature
+    /// Only available when the `stl-io` feature is enabled.
+    ///
+    pub fn stl<P: AsRef<Path>>(&self, path: P) -> Result<CPUMesh, IOError> {
+        let bytes = self.bytes(path.as_ref())?;
+        let (name, triangles) = if is_ascii_stl(bytes) {
+            parse_ascii_stl(bytes)?
+        } else {
+            parse_binary_stl(bytes)?
+        };
+
+        let mut positions = Vec::new();
+        let mut indices = Vec::new();
+        let mut map: HashMap<[u32; 3], u32> = HashMap::new();
+        for vertex in triangles.chunks(3) {
+            let key = [vertex[0].to_bits(), vertex[1].to_bits(), vertex[2].to_bits()];
+            let index = *map.entry(key).or_insert_with(|| {
+                positions.extend_from_slice(vertex);
+                positions.len() as u32 / 3 - 1
+            });
+            indices.push(index);
+        }
+
+        let mut cpu_mesh = CPUMesh {
+            name: name.unwrap_or(path.as_ref().file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default()),
+            positions,
+            indices: Some(indices),
+            ..Default::default()
+        };
+        cpu_mesh.compute_normals();
+        Ok(cpu_mesh)
+    }
+}
+
+// The size of a binary file with the given number of triangles, or None if the size does not fit in a usize
+fn binary_stl_size(count: usize) -> Option<usize> {
+    count.checked_mul(50).and_then(|n| n.checked_add(84))
+}
+
+fn is_ascii_stl(bytes: &[u8]) -> bool {
+    // Some binary files also start with 'solid' in the header, so also check that the size does not match a binary file
+    if bytes.len() >= 84 {
+        let count = u32::from_le_bytes([bytes[80], bytes[81], bytes[82], bytes[83]]) as usize;
+        if binary_stl_size(count) == Some(bytes.len()) {
+            return false;
+        }
+    }
+    bytes.starts_with(b"solid")
+}
+
+fn parse_binary_stl(bytes: &[u8]) -> Result<(Option<String>, Vec<f32>), IOError> {
+    if bytes.len() < 84 {
+        Err(IOError::FailedToLoad {message: "The binary STL file is too short to contain a header.".to_string()})?;
+    }
+    let count = u32::from_le_bytes([bytes[80], bytes[81], bytes[82], bytes[83]]) as usize;
+    let size = binary_stl_size(count).ok_or_else(||
+        IOError::FailedToLoad {message: format!("The binary STL file contains too many triangles ({}).", count)})?;
+    if bytes.len() < size {
+        Err(IOError::FailedToLoad {message: format!("The binary STL file should contain {} triangles, but is too short.", count)})?;
+    }
+    let mut positions = Vec::with_capacity(count * 9);
+    for triangle in 0..count {
+        // Skip the normal (12 bytes) and read the three vertices
+        let offset = 84 + triangle * 50 + 12;
+        for i in 0..9 {
+            let o = offset + i * 4;
+            positions.push(f32::from_le_bytes([bytes[o], bytes[o+1], bytes[o+2], bytes[o+3]]));
+        }
+    }
+    Ok((None, positions))
+}
+
+fn parse_ascii_stl(bytes: &[u8]) -> Result<(Option<String>, Vec<f32>), IOError> {
+    let text = String::from_utf8_lossy(bytes);
+    let name = text.lines().next()
+        .map(|line| line.trim_start_matches("solid").trim().to_string())
+        .filter(|name| !name.is_empty());
+    let mut positions = Vec::new();
+    let mut tokens = text.split_whitespace();
+    while let Some(token) = tokens.next() {
+        if token == "vertex" {
+            for _ in 0..3 {
+                let value = tokens.next().ok_or(
+                    IOError::FailedToLoad {message: "Unexpected end of ASCII STL file.".to_string()})?;
+                positions.push(value.parse::<f32>().map_err(|_|
+                    IOError::FailedToLoad {message: format!("Could not parse the vertex coordinate '{}' in ASCII STL file.", value)})?);
+            }
+        }
+    }
+    if positions.len() % 9 != 0 {
+        Err(IOError::FailedToLoad {message: "The ASCII STL file contains a facet without three vertices.".to_string()})?;
+    }
+    Ok((name, positions))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Saver {
+    ///
+    /// Saves the given mesh as a binary .stl file.
+    ///
+    /// # Feature
+    /// Only available when the `stl-io` feature is enabled.
+    ///
+    pub fn save_stl<P: AsRef<Path>>(path: P, cpu_mesh: &CPUMesh) -> Result<(), IOError>
+    {
+        let triangles = stl_triangles(cpu_mesh)?;
+        let mut bytes = vec![0u8; 80];
+        let header = format!("{} - saved by three-d", cpu_mesh.name);
+        let header_length = header.len().min(80);
+        bytes[..header_length].copy_from_slice(&header.as_bytes()[..header_length]);
+        use std::convert::TryFrom;
+        let count = u32::try_from(triangles.len()).map_err(|_|
+            IOError::FailedToSave {message: format!("A binary STL file cannot contain {} triangles.", triangles.len())})?;
+        bytes.extend_from_slice(&count.to_le_bytes());
+        for (normal, vertices) in triangles {
+            for value in normal.iter().chain(vertices.iter()) {
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
+            bytes.extend_from_slice(&[0, 0]);
+        }
+        Self::save_file(path, &bytes)
+    }
+
+    ///
+    /// Saves the given mesh as an ASCII .stl file.
+    ///
+    /// # Feature
+    /// Only available when the `stl-io` feature is enabled.
+    ///
+    pub fn save_stl_ascii<P: AsRef<Path>>(path: P, cpu_mesh: &CPUMesh) -> Result<(), IOError>
+    {
+        let name = cpu_mesh.name.replace(char::is_whitespace, "_");
+        let mut text = format!("solid {}\n", name);
+        for (n, v) in stl_triangles(cpu_mesh)? {
+            text.push_str(&format!("  facet normal {:e} {:e} {:e}\n    outer loop\n", n[0], n[1], n[2]));
+            for i in 0..3 {
+                text.push_str(&format!("      vertex {:e} {:e} {:e}\n", v[3*i], v[3*i+1], v[3*i+2]));
+            }
+            text.push_str("    endloop\n  endfacet\n");
+        }
+        text.push_str(&format!("endsolid {}\n", name));
+        Self::save_file(path, text.as_bytes())
+    }
+}
+
+// A face normal followed by the positions of the three vertices
+#[cfg(not(target_arch = "wasm32"))]
+type StlTriangle = ([f32; 3], [f32; 9]);
+
+#[cfg(not(target_arch = "wasm32"))]
+fn stl_triangles(cpu_mesh: &CPUMesh) -> Result<Vec<StlTriangle>, IOError> {
+    use crate::math::*;
+    if cpu_mesh.primitive_type != PrimitiveType::Triangles {
+        Err(IOError::FailedToSave {message: format!("The mesh {} is not a triangle mesh, which is required for STL files.", cpu_mesh.name)})?;
+    }
+    let vertex_count = cpu_mesh.positions.len() as u32 / 3;
+    let indices = cpu_mesh.indices.clone().unwrap_or((0..vertex_count).collect());
+    let mut triangles = Vec::with_capacity(indices.len() / 3);
+    for face in indices.chunks(3) {
+        if face.len() < 3 || face.iter().any(|i| *i >= vertex_count) {
+            Err(IOError::FailedToSave {message: format!("The mesh {} has invalid triangle indices.", cpu_mesh.name)})?;
+        }
+        let mut vertices = [0.0; 9];
+        for (i, index) in face.iter().enumerate() {
+            vertices[3*i..3*i+3].copy_from_slice(&cpu_mesh.positions[*index as usize * 3..*index as usize * 3 + 3]);
+        }
+        let p0 = vec3(vertices[0], vertices[1], vertices[2]);
+        let p1 = vec3(vertices[3], vertices[4], vertices[5]);
+        let p2 = vec3(vertices[6], vertices[7], vertices[8]);
+        let normal = (p1 - p0).cross(p2 - p0);
+        let normal = if normal.magnitude2() > 0.0 { normal.normalize() } else { normal };
+        triangles.push(([normal.x, normal.y, normal.z], vertices));
+    }
+    Ok(triangles)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ASCII_STL: &str = "solid square
+  facet normal 0 0 1
+    outer loop
+      vertex 0 0 0
+      vertex 1 0 0
+      vertex 1 1 0
+    endloop
+  endfacet
+  facet normal 0 0 1
+    outer loop
+      vertex 0 0 0
+      vertex 1 1 0
+      vertex 0 1 0
+    endloop
+  endfacet
+endsolid square
+";
+
+    fn binary_stl(header: &[u8], triangles: &[[f32; 9]]) -> Vec<u8> {
+        let mut bytes = vec![0u8; 80];
+        bytes[..header.len()].copy_from_slice(header);
+        bytes.extend_from_slice(&(triangles.len() as u32).to_le_bytes());
+        for triangle in triangles {
+            bytes.extend_from_slice(&[0u8; 12]);
+            triangle.iter().for_each(|v| bytes.extend_from_slice(&v.to_le_bytes()));
+            bytes.extend_from_slice(&[0, 0]);
+        }
+        bytes
+    }
+
+    fn load(path: &str, bytes: Vec<u8>) -> CPUMesh {
+        let mut loaded = HashMap::new();
+        loaded.insert(std::path::PathBuf::from(path), Ok(bytes));
+        Loaded::new(loaded).stl(path).unwrap()
+    }
+
+    fn check_square(cpu_mesh: &CPUMesh) {
+        // The two shared vertices are welded
+        assert_eq!(cpu_mesh.positions, vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 1.0, 0.0]);
+        assert_eq!(cpu_mesh.indices, Some(vec![0, 1, 2, 0, 2, 3]));
+        assert_eq!(cpu_mesh.normals, Some(vec![0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0]));
+    }
+
+    #[test]
+    fn load_ascii_stl() {
+        assert!(is_ascii_stl(ASCII_STL.as_bytes()));
+        let cpu_mesh = load("model.stl", ASCII_STL.as_bytes().to_vec());
+        assert_eq!(cpu_mesh.name, "square");
+        check_square(&cpu_mesh);
+    }
+
+    #[test]
+    fn load_binary_stl() {
+        let triangles = [[0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0, 0.0], [0.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 1.0, 0.0]];
+        // Binary files which start with 'solid' are detected by their size
+        let bytes = binary_stl(b"solid square", &triangles);
+        assert!(!is_ascii_stl(&bytes));
+        let cpu_mesh = load("model.stl", bytes);
+        assert_eq!(cpu_mesh.name, "model");
+        check_square(&cpu_mesh);
+    }
+
+    #[test]
+    fn load_truncated_binary_stl() {
+        let mut bytes = binary_stl(b"binary", &[[0.0; 9]]);
+        bytes.truncate(100);
+        let mut loaded = HashMap::new();
+        loaded.insert(std::path::PathBuf::from("model.stl"), Ok(bytes));
+        assert!(Loaded::new(loaded).stl("model.stl").is_err());
+    }
+
+    #[test]
+    fn load_binary_stl_with_invalid_count() {
+        let mut bytes = binary_stl(b"binary", &[[0.0; 9]]);
+        bytes[80..84].copy_from_slice(&u32::MAX.to_le_bytes());
+        let mut loaded = HashMap::new();
+        loaded.insert(std::path::PathBuf::from("model.stl"), Ok(bytes));
+        assert!(Loaded::new(loaded).stl("model.stl").is_err());
+        assert_eq!(binary_stl_size(usize::MAX / 50 + 1), None);
+    }
+}
@@ -3,7 +3,7 @@ use std::path::Path;
 use crate::io::*;
 use crate::definition::*;
 
-impl<'a> Loaded<'a> {
+impl Loaded {
     ///
     /// Deserialize the loaded image resource at the given path into a [CPUTexture](crate::CPUTexture) using
     /// the [image](https://crates.io/crates/image/main.rs) crate.
@@ -12,19 +12,22 @@ impl<'a> Loaded<'a> {
     /// # Feature
     /// Only available when the `image-io` feature is enabled.
     ///
-    pub fn image<P: AsRef<Path>>(&'a self, path: P) -> Result<CPUTexture<u8>, IOError> {
-        use image::GenericImageView;
-        let img = image::load_from_memory(self.bytes(path)?)?;
-        let bytes = img.to_bytes();
-        let number_of_channels = bytes.len() / (img.width() * img.height()) as usize;
-        let format = match number_of_channels {
-            1 => Ok(Format::R8),
-            3 => Ok(Format::RGB8),
-            4 => Ok(Format::RGBA8),
-            _ => Err(IOError::FailedToLoad {message: format!("Could not determine the pixel format for the texture.")})
-        }?;
+    pub fn image<P: AsRef<Path>>(&self, path: P) -> Result<CPUTexture<u8>, IOError> {
+        image_from_bytes(self.bytes(path)?)
+    }
 
-        Ok(CPUTexture {data: bytes, width: img.width() as usize, height: img.height() as usize, format, ..Default::default()})
+    ///
+    /// Deserialize the loaded image resource at the given path into a [CPUTexture](crate::CPUTexture) with floating point values using
+    /// the [image](https://crates.io/crates/image/main.rs) crate.
+    /// High dynamic range (.hdr) images keep their values, while 8 and 16 bit images are mapped to the range [0..1].
+    /// The format of the CPUTexture is either RGB32F or RGBA32F depending on whether the image has an alpha channel.
+    /// The CPUTexture can then be used to create a [Texture2D](crate::Texture2D).
+    ///
+    /// # Feature
+    /// Only available when the `image-io` feature is enabled.
+    ///
+    pub fn image_f32<P: AsRef<Path>>(&self, path: P) -> Result<CPUTexture<f32>, IOError> {
+        image_f32_from_bytes(self.bytes(path)?)
     }
 
     ///
@@ -35,22 +38,122 @@ impl<'a> Loaded<'a> {
     /// # Feature
     /// Only available when the `image-io` feature is enabled.
     ///
-    pub fn cube_image<P: AsRef<Path>>(&'a self, right_path: P, left_path: P,
+    pub fn cube_image<P: AsRef<Path>>(&self, right_path: P, left_path: P,
                                       top_path: P, bottom_path: P, front_path: P, back_path: P) -> Result<CPUTexture<u8>, IOError> {
-        let mut right = self.image(right_path)?;
-        let left = self.image(left_path)?;
-        let top = self.image(top_path)?;
-        let bottom = self.image(bottom_path)?;
-        let front = self.image(front_path)?;
-        let back = self.image(back_path)?;
-
-        right.data.extend(left.data);
-        right.data.extend(top.data);
-        right.data.extend(bottom.data);
-        right.data.extend(front.data);
-        right.data.extend(back.data);
+        let mut right = self.image(&right_path)?;
+        if right.width != right.height {
+            Err(IOError::FailedToLoad {message: format!("The cube map face {:?} is {}x{} pixels, but cube map faces must be square.",
+                                                        right_path.as_ref(), right.width, right.height)})?;
+        }
+        for path in [left_path, top_path, bottom_path, front_path, back_path].iter() {
+            let face = self.image(path)?;
+            if face.width != right.width || face.height != right.height || face.format != right.format {
+                Err(IOError::FailedToLoad {message: format!(
+                    "The cube map face {:?} is {}x{} pixels with format {:?}, which does not match the right face {:?} which is {}x{} pixels with format {:?}.",
+                    path.as_ref(), face.width, face.height, face.format, right_path.as_ref(), right.width, right.height, right.format)})?;
+            }
+            right.data.extend(face.data);
+        }
         Ok(right)
     }
+
+    ///
+    /// Deserialize a loaded image resource containing all six faces of a cube map into a [CPUTexture](crate::CPUTexture) using
+    /// the [image](https://crates.io/crates/image/main.rs) crate.
+    /// The layout is determined from the aspect ratio of the image and can be either
+    /// - a horizontal cross (4:3) with the top and bottom faces above and below the front face in the order left, front, right, back,
+    /// - a vertical cross (3:4) with the faces top, left, front, right, bottom and back (upside down) from top to bottom,
+    /// - a horizontal (6:1) or vertical (1:6) strip with the faces in the order right, left, top, bottom, front, back.
+    ///
+    /// The CPUTexture can then be used to create a [TextureCubeMap](crate::TextureCubeMap).
+    ///
+    /// # Feature
+    /// Only available when the `image-io` feature is enabled.
+    ///
+    pub fn cube_image_from_layout<P: AsRef<Path>>(&self, path: P) -> Result<CPUTexture<u8>, IOError> {
+        let image = self.image(path.as_ref())?;
+        let (width, height) = (image.width, image.height);
+        // The column and row of the right, left, top, bottom, front and back faces and whether the face is upside down
+        let (size, faces) = if width * 3 == height * 4 {
+            (width / 4, [(2, 1, false), (0, 1, false), (1, 0, false), (1, 2, false), (1, 1, false), (3, 1, false)])
+        } else if width * 4 == height * 3 {
+            (width / 3, [(2, 1, false), (0, 1, false), (1, 0, false), (1, 2, false), (1, 1, false), (1, 3, true)])
+        } else if width == height * 6 {
+            (height, [(0, 0, false), (1, 0, false), (2, 0, false), (3, 0, false), (4, 0, false), (5, 0, false)])
+        } else if width * 6 == height {
+            (width, [(0, 0, false), (0, 1, false), (0, 2, false), (0, 3, false), (0, 4, false), (0, 5, false)])
+        } else {
+            Err(IOError::FailedToLoad {message: format!(
+                "The cube map image {:?} is {}x{} pixels, which is neither a horizontal cross (4:3), a vertical cross (3:4) or a strip (6:1 or 1:6).",
+                path.as_ref(), width, height)})?
+        };
+        if size == 0 {
+            Err(IOError::FailedToLoad {message: format!("The cube map image {:?} is empty.", path.as_ref())})?;
+        }
+
+        let channels = image.data.len() / (width * height);
+        let mut data = Vec::with_capacity(6 * size * size * channels);
+        for (col, row, upside_down) in faces.iter() {
+            for y in 0..size {
+                for x in 0..size {
+                    let (x, y) = if *upside_down { (size - 1 - x, size - 1 - y) } else { (x, y) };
+                    let offset = ((row * size + y) * width + col * size + x) * channels;
+                    data.extend_from_slice(&image.data[offset..offset + channels]);
+                }
+            }
+        }
+        Ok(CPUTexture {data, width: size, height: size, ..image})
+    }
+}
+
+pub(crate) fn image_from_bytes(bytes: &[u8]) -> Result<CPUTexture<u8>, IOError> {
+    use image::GenericImageView;
+    let img = image::load_from_memory(bytes)?;
+    let data = img.to_bytes();
+    let number_of_channels = data.len() / (img.width() * img.height()) as usize;
+    let format = match number_of_channels {
+        1 => Ok(Format::R8),
+        3 => Ok(Format::RGB8),
+        4 => Ok(Format::RGBA8),
+        _ => Err(IOError::FailedToLoad {message: format!("Could not determine the pixel format for the texture.")})
+    }?;
+
+    Ok(CPUTexture {data, width: img.width() as usize, height: img.height() as usize, format, ..Default::default()})
+}
+
+pub(crate) fn image_f32_from_bytes(bytes: &[u8]) -> Result<CPUTexture<f32>, IOError> {
+    use image::GenericImageView;
+    if image::guess_format(bytes)? == image::ImageFormat::Hdr {
+        let decoder = image::hdr::HdrDecoder::new(bytes)?;
+        let metadata = decoder.metadata();
+        let data = decoder.read_image_hdr()?.iter().flat_map(|pixel| pixel.0.to_vec()).collect();
+        return Ok(CPUTexture {data, width: metadata.width as usize, height: metadata.height as usize, format: Format::RGB32F, ..Default::default()});
+    }
+
+    let img = image::load_from_memory(bytes)?;
+    let color = img.color();
+    let format = if color.has_alpha() { Format::RGBA32F } else { Format::RGB32F };
+    let data = if color.bytes_per_pixel() / color.channel_count() > 1 {
+        let data = if color.has_alpha() { img.to_rgba16().into_raw() } else { img.to_rgb16().into_raw() };
+        data.iter().map(|value| *value as f32 / u16::MAX as f32).collect()
+    } else {
+        let data = if color.has_alpha() { img.to_rgba8().into_raw() } else { img.to_rgb8().into_raw() };
+        data.iter().map(|value| *value as f32 / u8::MAX as f32).collect()
+    };
+    Ok(CPUTexture {data, width: img.width() as usize, height: img.height() as usize, format, ..Default::default()})
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn png_from_texture(cpu_texture: &CPUTexture<u8>) -> Result<Vec<u8>, IOError> {
+    let color_type = match cpu_texture.format {
+        Format::R8 => Ok(image::ColorType::L8),
+        Format::RGB8 | Format::SRGB8 => Ok(image::ColorType::Rgb8),
+        Format::RGBA8 | Format::SRGBA8 => Ok(image::ColorType::Rgba8),
+        _ => Err(IOError::FailedToSave {message: format!("Texture with format {:?} could not be encoded as png", cpu_texture.format)})
+    }?;
+    let mut bytes = Vec::new();
+    image::png::PngEncoder::new(&mut bytes).encode(&cpu_texture.data, cpu_texture.width as u32, cpu_texture.height as u32, color_type)?;
+    Ok(bytes)
 }
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -76,4 +179,359 @@ impl Saver {
         image::save_buffer(path, &pixels_out, width as u32, height as u32, image::ColorType::Rgb8)?;
         Ok(())
     }
-}
\ No newline at end of file
+
+    ///
+    /// Saves the given RGBA pixels as an image, for example read using [Screen::read_color_rgba](crate::Screen::read_color_rgba).
+    /// The alpha channel is kept, so the image format should support transparency (for example .png).
+    ///
+    /// # Feature
+    /// Only available when the `image-io` feature is enabled.
+    ///
+    pub fn save_pixels_rgba<P: AsRef<Path>>(path: P, pixels: &[u8], width: usize, height: usize) -> Result<(), IOError>
+    {
+        let pixels_out = flip_rows(pixels, width * 4, height)?;
+        image::save_buffer(path, &pixels_out, width as u32, height as u32, image::ColorType::Rgba8)?;
+        Ok(())
+    }
+
+    ///
+    /// Saves the given depth values, for example read using [Screen::read_depth](crate::Screen::read_depth), as a grayscale image.
+    /// The depth values are normalized so the closest depth is black and the farthest depth (except the cleared depth 1.0) is white.
+    /// Use [save_pixels_f32](Self::save_pixels_f32) to save the raw depth values.
+    ///
+    /// # Feature
+    /// Only available when the `image-io` feature is enabled.
+    ///
+    pub fn save_depth<P: AsRef<Path>>(path: P, depth: &[f32], width: usize, height: usize) -> Result<(), IOError>
+    {
+        let depth = flip_rows(depth, width, height)?;
+        let (min, max) = depth.iter().filter(|d| **d < 1.0)
+            .fold((f32::MAX, f32::MIN), |(min, max), d| (min.min(*d), max.max(*d)));
+        let pixels: Vec<u8> = depth.iter().map(|d| {
+            if *d >= 1.0 || max <= min { 255 } else { (255.0 * (d - min) / (max - min)) as u8 }
+        }).collect();
+        image::save_buffer(path, &pixels, width as u32, height as u32, image::ColorType::L8)?;
+        Ok(())
+    }
+
+    ///
+    /// Saves the given floating point pixels, for example read using [RenderTarget::read_color_f32](crate::RenderTarget::read_color_f32)
+    /// or [Screen::read_depth](crate::Screen::read_depth), as a high dynamic range image.
+    /// The format must be [R32F](crate::Format::R32F), [RGB32F](crate::Format::RGB32F) or [RGBA32F](crate::Format::RGBA32F)
+    /// and the file type is chosen by the extension of the path, either OpenEXR (.exr) or Radiance HDR (.hdr).
+    /// The values are saved without any conversion, except that Radiance HDR only supports RGB,
+    /// so a single channel is saved as gray and the alpha channel is discarded.
+    ///
+    /// # Feature
+    /// Only available when the `image-io` feature is enabled.
+    ///
+    pub fn save_pixels_f32<P: AsRef<Path>>(path: P, pixels: &[f32], width: usize, height: usize, format: Format) -> Result<(), IOError>
+    {
+        let channel_count = match format {
+            Format::R32F => 1,
+            Format::RGB32F => 3,
+            Format::RGBA32F => 4,
+            _ => Err(IOError::FailedToSave {message: format!("Cannot save floating point pixels with format {:?}.", format)})?
+        };
+        let pixels = flip_rows(pixels, width * channel_count, height)?;
+        let extension = path.as_ref().extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default();
+        match extension.as_str() {
+            "exr" => Self::save_file(path, &encode_exr(&pixels, width, height, channel_count)),
+            "hdr" => {
+                let rgb: Vec<image::Rgb<f32>> = pixels.chunks(channel_count).map(|pixel| match channel_count {
+                    1 => image::Rgb([pixel[0], pixel[0], pixel[0]]),
+                    _ => image::Rgb([pixel[0], pixel[1], pixel[2]])
+                }).collect();
+                let mut bytes = Vec::new();
+                image::hdr::HdrEncoder::new(&mut bytes).encode(&rgb, width, height)?;
+                Self::save_file(path, &bytes)
+            },
+            _ => Err(IOError::FailedToSave {message: format!("Floating point pixels can only be saved as .exr or .hdr, not as {}.", path.as_ref().display())})
+        }
+    }
+}
+
+// Flips the rows, since the pixels read from a render target starts with the bottom row while images start with the top row
+#[cfg(not(target_arch = "wasm32"))]
+fn flip_rows<T: Copy>(pixels: &[T], row_length: usize, height: usize) -> Result<Vec<T>, IOError> {
+    if pixels.len() != row_length * height {
+        Err(IOError::FailedToSave {message: format!("Expected {} values but got {}.", row_length * height, pixels.len())})?;
+    }
+    Ok(pixels.chunks(row_length.max(1)).rev().flatten().copied().collect())
+}
+
+// Encodes the pixels as an uncompressed scanline OpenEXR image with 32-bit float channels
+#[cfg(not(target_arch = "wasm32"))]
+fn encode_exr(pixels: &[f32], width: usize, height: usize, channel_count: usize) -> Vec<u8> {
+    // The channels must be sorted alphabetically, the index is the channel index in the pixels
+    let channels: &[(&str, usize)] = match channel_count {
+        1 => &[("Y", 0)],
+        3 => &[("B", 2), ("G", 1), ("R", 0)],
+        _ => &[("A", 3), ("B", 2), ("G", 1), ("R", 0)]
+    };
+
+    let mut bytes = vec![0x76, 0x2f, 0x31, 0x01, 2, 0, 0, 0];
+    let mut attribute = |name: &str, kind: &str, value: &[u8]| {
+        for text in [name, kind] {
+            bytes.extend_from_slice(text.as_bytes());
+            bytes.push(0);
+        }
+        bytes.extend_from_slice(&(value.len() as i32).to_le_bytes());
+        bytes.extend_from_slice(value);
+    };
+    let mut channel_list = Vec::new();
+    for (name, _) in channels {
+        channel_list.extend_from_slice(name.as_bytes());
+        channel_list.push(0);
+        // Pixel type float, linear flag and reserved bytes, x and y sampling
+        for value in [2i32, 0, 1, 1] {
+            channel_list.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+    channel_list.push(0);
+    let window: Vec<u8> = [0, 0, width as i32 - 1, height as i32 - 1].iter().flat_map(|v| v.to_le_bytes()).collect();
+    attribute("channels", "chlist", &channel_list);
+    attribute("compression", "compression", &[0]);
+    attribute("dataWindow", "box2i", &window);
+    attribute("displayWindow", "box2i", &window);
+    attribute("lineOrder", "lineOrder", &[0]);
+    attribute("pixelAspectRatio", "float", &1.0f32.to_le_bytes());
+    attribute("screenWindowCenter", "v2f", &[0; 8]);
+    attribute("screenWindowWidth", "float", &1.0f32.to_le_bytes());
+    bytes.push(0);
+
+    // The offset table followed by one chunk for each scanline, each containing all of the values for one channel followed by the next channel
+    let line_size = width * channels.len() * 4;
+    let table_end = bytes.len() + height * 8;
+    for y in 0..height {
+        bytes.extend_from_slice(&((table_end + y * (line_size + 8)) as u64).to_le_bytes());
+    }
+    for y in 0..height {
+        bytes.extend_from_slice(&(y as i32).to_le_bytes());
+        bytes.extend_from_slice(&(line_size as i32).to_le_bytes());
+        for (_, channel) in channels {
+            for x in 0..width {
+                bytes.extend_from_slice(&pixels[(y * width + x) * channel_count + channel].to_le_bytes());
+            }
+        }
+    }
+    bytes
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::convert::TryInto;
+
+    fn test_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("three-d-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn loaded(path: &str, bytes: Vec<u8>) -> Loaded {
+        let mut loaded = HashMap::new();
+        loaded.insert(std::path::PathBuf::from(path), Ok(bytes));
+        Loaded::new(loaded)
+    }
+
+    #[test]
+    fn load_image_f32() {
+        let mut hdr = Vec::new();
+        image::hdr::HdrEncoder::new(&mut hdr).encode(&[image::Rgb([0.5, 2.0, 8.0]), image::Rgb([64.0, 16.0, 1.0])], 2, 1).unwrap();
+        let image = loaded("image.hdr", hdr).image_f32("image.hdr").unwrap();
+        assert_eq!((image.width, image.height, image.format), (2, 1, Format::RGB32F));
+        assert_eq!(image.data, vec![0.5, 2.0, 8.0, 64.0, 16.0, 1.0]);
+
+        // 16 bit images are mapped to the range [0..1] and keep the alpha channel
+        let pixel: Vec<u8> = [0u16, 32768, 65535, 65535].iter().flat_map(|v| v.to_be_bytes()).collect();
+        let image = loaded("image.png", png(1, 1, &pixel, image::ColorType::Rgba16)).image_f32("image.png").unwrap();
+        assert_eq!(image.format, Format::RGBA32F);
+        assert_eq!(image.data, vec![0.0, 32768.0 / 65535.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn load_unsupported_image_f32() {
+        // OpenEXR images can be saved, but not loaded
+        let exr = encode_exr(&[1.0, 2.0, 3.0], 1, 1, 3);
+        assert!(loaded("image.exr", exr).image_f32("image.exr").is_err());
+        assert!(loaded("image.txt", b"not an image".to_vec()).image_f32("image.txt").is_err());
+    }
+
+    fn png(width: usize, height: usize, data: &[u8], color_type: image::ColorType) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        image::png::PngEncoder::new(&mut bytes).encode(data, width as u32, height as u32, color_type).unwrap();
+        bytes
+    }
+
+    // Creates a grayscale image with 2x2 pixel faces at the given column and row, optionally upside down,
+    // where each pixel has the value 10 * face + 2 * y + x in the face
+    fn cube_layout(columns: usize, rows: usize, faces: [(usize, usize, bool); 6]) -> Vec<u8> {
+        let width = columns * 2;
+        let mut data = vec![255u8; width * rows * 2];
+        for (face, (col, row, upside_down)) in faces.iter().enumerate() {
+            for y in 0..2 {
+                for x in 0..2 {
+                    let (px, py) = if *upside_down { (1 - x, 1 - y) } else { (x, y) };
+                    data[(row * 2 + py) * width + col * 2 + px] = (10 * face + 2 * y + x) as u8;
+                }
+            }
+        }
+        png(width, rows * 2, &data, image::ColorType::L8)
+    }
+
+    #[test]
+    fn load_cube_image_from_layout() {
+        let expected: Vec<u8> = (0..6).flat_map(|face| (0..4).map(move |i| 10 * face + i)).collect();
+        let layouts = [
+            ("horizontal_cross.png", cube_layout(4, 3, [(2, 1, false), (0, 1, false), (1, 0, false), (1, 2, false), (1, 1, false), (3, 1, false)])),
+            ("vertical_cross.png", cube_layout(3, 4, [(2, 1, false), (0, 1, false), (1, 0, false), (1, 2, false), (1, 1, false), (1, 3, true)])),
+            ("horizontal_strip.png", cube_layout(6, 1, [(0, 0, false), (1, 0, false), (2, 0, false), (3, 0, false), (4, 0, false), (5, 0, false)])),
+            ("vertical_strip.png", cube_layout(1, 6, [(0, 0, false), (0, 1, false), (0, 2, false), (0, 3, false), (0, 4, false), (0, 5, false)]))
+        ];
+        for (path, bytes) in layouts.iter() {
+            let cube = loaded(path, bytes.clone()).cube_image_from_layout(path).unwrap();
+            assert_eq!((cube.width, cube.height, cube.format), (2, 2, Format::R8), "{}", path);
+            assert_eq!(cube.data, expected, "{}", path);
+        }
+
+        let invalid = png(5, 3, &[0; 15], image::ColorType::L8);
+        assert!(loaded("invalid.png", invalid).cube_image_from_layout("invalid.png").is_err());
+    }
+
+    #[test]
+    fn load_cube_image() {
+        let mut loaded = HashMap::new();
+        for (i, name) in ["right", "left", "top", "bottom", "front", "back"].iter().enumerate() {
+            loaded.insert(std::path::PathBuf::from(format!("{}.png", name)), Ok(png(1, 1, &[i as u8], image::ColorType::L8)));
+        }
+        loaded.insert(std::path::PathBuf::from("wide.png"), Ok(png(2, 1, &[0, 0], image::ColorType::L8)));
+        loaded.insert(std::path::PathBuf::from("large.png"), Ok(png(2, 2, &[0; 4], image::ColorType::L8)));
+        loaded.insert(std::path::PathBuf::from("rgb.png"), Ok(png(1, 1, &[0; 3], image::ColorType::Rgb8)));
+        let loaded = Loaded::new(loaded);
+
+        let cube = loaded.cube_image("right.png", "left.png", "top.png", "bottom.png", "front.png", "back.png").unwrap();
+        assert_eq!((cube.width, cube.height, cube.data), (1, 1, vec![0, 1, 2, 3, 4, 5]));
+
+        // The faces must be square and have the same size and format
+        assert!(loaded.cube_image("wide.png", "wide.png", "wide.png", "wide.png", "wide.png", "wide.png").is_err());
+        assert!(loaded.cube_image("right.png", "left.png", "top.png", "large.png", "front.png", "back.png").is_err());
+        assert!(loaded.cube_image("right.png", "left.png", "top.png", "bottom.png", "rgb.png", "back.png").is_err());
+    }
+
+    // Decodes an uncompressed scanline OpenEXR image with 32-bit float channels into the size and the values of each channel
+    fn decode_exr(bytes: &[u8]) -> (usize, usize, Vec<(String, Vec<f32>)>) {
+        assert_eq!(&bytes[..8], &[0x76, 0x2f, 0x31, 0x01, 2, 0, 0, 0]);
+        let i32_at = |offset: usize| i32::from_le_bytes([bytes[offset], bytes[offset + 1], bytes[offset + 2], bytes[offset + 3]]);
+        let text_at = |offset: usize| {
+            let length = bytes[offset..].iter().position(|b| *b == 0).unwrap();
+            (String::from_utf8(bytes[offset..offset + length].to_vec()).unwrap(), offset + length + 1)
+        };
+
+        let mut attributes = HashMap::new();
+        let mut offset = 8;
+        loop {
+            let (name, next) = text_at(offset);
+            if name.is_empty() {
+                offset = next;
+                break;
+            }
+            let (_, next) = text_at(next);
+            let size = i32_at(next) as usize;
+            attributes.insert(name, bytes[next + 4..next + 4 + size].to_vec());
+            offset = next + 4 + size;
+        }
+        assert_eq!(attributes["compression"], vec![0]);
+        let window = &attributes["dataWindow"];
+        let width = i32::from_le_bytes([window[8], window[9], window[10], window[11]]) as usize + 1;
+        let height = i32::from_le_bytes([window[12], window[13], window[14], window[15]]) as usize + 1;
+
+        let mut channels = Vec::new();
+        let channel_list = &attributes["channels"];
+        let mut channel_offset = 0;
+        while channel_list[channel_offset] != 0 {
+            let length = channel_list[channel_offset..].iter().position(|b| *b == 0).unwrap();
+            let name = String::from_utf8(channel_list[channel_offset..channel_offset + length].to_vec()).unwrap();
+            // The pixel type must be float
+            assert_eq!(channel_list[channel_offset + length + 1], 2);
+            channels.push((name, vec![0.0; width * height]));
+            channel_offset += length + 1 + 16;
+        }
+
+        for row in 0..height {
+            let line_offset = u64::from_le_bytes(bytes[offset + row * 8..offset + row * 8 + 8].try_into().unwrap()) as usize;
+            let y = i32_at(line_offset) as usize;
+            assert_eq!(i32_at(line_offset + 4) as usize, width * channels.len() * 4);
+            for (c, (_, values)) in channels.iter_mut().enumerate() {
+                for x in 0..width {
+                    let value_offset = line_offset + 8 + (c * width + x) * 4;
+                    values[y * width + x] = f32::from_le_bytes(bytes[value_offset..value_offset + 4].try_into().unwrap());
+                }
+            }
+        }
+        (width, height, channels)
+    }
+
+    #[test]
+    fn save_exr() {
+        let dir = test_dir("save-exr");
+        // Two rows of three pixels, starting with the bottom row, with values outside of the range [0..1]
+        let pixels: Vec<f32> = (0..24).map(|i| i as f32 * 0.5 - 2.0).collect();
+        Saver::save_pixels_f32(dir.join("image.exr"), &pixels, 3, 2, Format::RGBA32F).unwrap();
+        let (width, height, channels) = decode_exr(&std::fs::read(dir.join("image.exr")).unwrap());
+        assert_eq!((width, height), (3, 2));
+        assert_eq!(channels.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>(), vec!["A", "B", "G", "R"]);
+        for (name, values) in channels.iter() {
+            let channel = match name.as_str() { "R" => 0, "G" => 1, "B" => 2, _ => 3 };
+            for y in 0..height {
+                for x in 0..width {
+                    assert_eq!(values[y * width + x], pixels[((height - 1 - y) * width + x) * 4 + channel]);
+                }
+            }
+        }
+
+        Saver::save_pixels_f32(dir.join("depth.exr"), &[0.25, 0.5], 1, 2, Format::R32F).unwrap();
+        let (_, _, channels) = decode_exr(&std::fs::read(dir.join("depth.exr")).unwrap());
+        assert_eq!(channels, vec![("Y".to_string(), vec![0.5, 0.25])]);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn save_hdr() {
+        let dir = test_dir("save-hdr");
+        // Powers of two which can be represented exactly with a shared exponent
+        Saver::save_pixels_f32(dir.join("image.hdr"), &[0.5, 2.0, 8.0, 1.0, 4.0, 16.0], 1, 2, Format::RGB32F).unwrap();
+        Saver::save_pixels_f32(dir.join("gray.hdr"), &[2.0, 4.0], 2, 1, Format::R32F).unwrap();
+        let image = image_f32_from_bytes(&std::fs::read(dir.join("image.hdr")).unwrap()).unwrap();
+        assert_eq!(image.data, vec![1.0, 4.0, 16.0, 0.5, 2.0, 8.0]);
+        let gray = image_f32_from_bytes(&std::fs::read(dir.join("gray.hdr")).unwrap()).unwrap();
+        assert_eq!(gray.data, vec![2.0, 2.0, 2.0, 4.0, 4.0, 4.0]);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn save_depth() {
+        let dir = test_dir("save-depth");
+        // The cleared depth 1.0 is white, the rest is normalized between the closest and farthest depth
+        Saver::save_depth(dir.join("depth.png"), &[0.25, 0.5, 0.75, 1.0], 2, 2).unwrap();
+        let image = image::open(dir.join("depth.png")).unwrap().to_luma8();
+        assert_eq!(image.into_raw(), vec![255, 255, 0, 127]);
+
+        // All values are white if there is no range to normalize
+        Saver::save_depth(dir.join("flat.png"), &[0.5, 0.5], 2, 1).unwrap();
+        assert_eq!(image::open(dir.join("flat.png")).unwrap().to_luma8().into_raw(), vec![255, 255]);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn save_wrong_length() {
+        let dir = test_dir("save-wrong-length");
+        assert!(Saver::save_pixels_rgba(dir.join("image.png"), &[0; 7], 2, 1).is_err());
+        assert!(Saver::save_depth(dir.join("depth.png"), &[0.0; 3], 2, 2).is_err());
+        assert!(Saver::save_pixels_f32(dir.join("image.exr"), &[0.0; 5], 2, 1, Format::RGB32F).is_err());
+        assert!(Saver::save_pixels_f32(dir.join("image.exr"), &[0.0; 2], 2, 1, Format::R8).is_err());
+        assert!(Saver::save_pixels_f32(dir.join("image.png"), &[0.0; 2], 2, 1, Format::R32F).is_err());
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,45 +1,45 @@
 use crate::io::*;
 use std::path::Path;
+use std::rc::Rc;
 use crate::definition::*;
+use crate::math::*;
 
-impl<'a> Loaded<'a> {
+impl Loaded {
     ///
     /// Deserialize a loaded .3d file resource (a custom binary format for `three-d`) into a list of meshes and materials.
+    /// Textures which are not embedded in the .3d file must also be loaded, they are placed next to the .3d file.
     ///
     /// # Feature
     /// Only available when the `3d-io` feature is enabled.
     ///
-    pub fn three_d<P: AsRef<Path>>(&'a self, path: P) -> Result<(Vec<CPUMesh>, Vec<CPUMaterial>), IOError>
+    pub fn three_d<P: AsRef<Path>>(&self, path: P) -> Result<(Vec<CPUMesh>, Vec<CPUMaterial>), IOError>
     {
-        let bytes = self.bytes(path.as_ref())?;
-        let mut decoded = bincode::deserialize::<ThreeDMesh>(bytes)
-            .or_else(|_| Self::deserialize_version1(bytes))?;
-
-        if decoded.meshes.len() == 0 {
-            decoded = Self::deserialize_version1(bytes)?;
-        }
-
-        if decoded.magic_number != 61 {
-            Err(bincode::Error::new(bincode::ErrorKind::Custom("Corrupt file!".to_string())))?;
-        }
-
-        if decoded.meshes.len() == 0 {
-            Err(bincode::Error::new(bincode::ErrorKind::Custom("No mesh data in file!".to_string())))?;
-        }
+        let decoded = Self::deserialize_3d(self.bytes(path.as_ref())?)?;
 
         let mut cpu_meshes = Vec::new();
         for mesh in decoded.meshes {
             cpu_meshes.push(CPUMesh {
                 name: mesh.name,
                 material_name: mesh.material_name,
+                primitive_type: mesh.primitive_type.into(),
                 positions: mesh.positions,
                 indices: mesh.indices,
                 normals: mesh.normals,
+                tangents: mesh.tangents,
                 uvs: mesh.uvs,
-                colors: None
+                colors: mesh.colors
             });
         }
 
+        let dir = path.as_ref().parent().unwrap_or(&Path::new("./"));
+        let load_texture = |texture: Option<ThreeDTexture>| -> Result<Option<Rc<CPUTexture<u8>>>, IOError> {
+            match texture {
+                Some(ThreeDTexture::Path(filename)) => Ok(Some(self.shared_image(dir.join(filename))?)),
+                Some(ThreeDTexture::Embedded(bytes)) => Ok(Some(Rc::new(image_from_bytes(&bytes)?))),
+                None => Ok(None)
+            }
+        };
+
         let mut cpu_materials = Vec::new();
         for material in decoded.materials {
             cpu_materials.push(CPUMaterial {
@@ -48,20 +48,81 @@ impl<'a> Loaded<'a> {
                 diffuse_intensity: material.diffuse_intensity,
                 specular_intensity: material.specular_intensity,
                 specular_power: material.specular_power,
-                texture_image: if let Some(filename) = material.texture_path {
-                    let texture_path = path.as_ref().parent().unwrap_or(&Path::new("./")).join(filename);
-                    Some(self.image(&texture_path)?)
-                } else { None }
+                emissive_color: material.emissive_color,
+                texture_image: load_texture(material.texture)?,
+                specular_image: load_texture(material.specular_texture)?,
+                bump_image: load_texture(material.bump_texture)?,
+                opacity_image: load_texture(material.opacity_texture)?
             });
         }
         Ok((cpu_meshes, cpu_materials))
     }
 
-    fn deserialize_version1(bytes: &[u8]) -> Result<ThreeDMesh, bincode::Error> {
-        bincode::deserialize::<ThreeDMeshV1>(bytes).map(|m| ThreeDMesh {
+    ///
+    /// Deserialize the cameras and lights in a loaded .3d file resource.
+    /// Files saved before version 3 of the format do not contain any cameras or lights.
+    ///
+    /// # Feature
+    /// Only available when the `3d-io` feature is enabled.
+    ///
+    pub fn three_d_cameras_and_lights<P: AsRef<Path>>(&self, path: P) -> Result<(Vec<CPUCamera>, Vec<CPULight>), IOError>
+    {
+        let decoded = Self::deserialize_3d(self.bytes(path.as_ref())?)?;
+        let cameras = decoded.cameras.into_iter().map(|camera| CPUCamera {
+            name: camera.name,
+            position: Vec3::from(camera.position),
+            target: Vec3::from(camera.target),
+            up: Vec3::from(camera.up),
+            projection: match camera.projection {
+                ThreeDProjection::Perspective {field_of_view_y, z_near, z_far} =>
+                    CPUProjection::Perspective {field_of_view_y: degrees(field_of_view_y), z_near, z_far},
+                ThreeDProjection::Orthographic {width, height, depth} => CPUProjection::Orthographic {width, height, depth}
+            }
+        }).collect();
+        let lights = decoded.lights.into_iter().map(|light| match light {
+            ThreeDLight::Ambient {intensity, color} => CPULight::Ambient {intensity, color: Vec3::from(color)},
+            ThreeDLight::Directional {intensity, color, direction} =>
+                CPULight::Directional {intensity, color: Vec3::from(color), direction: Vec3::from(direction)},
+            ThreeDLight::Point {intensity, color, position, attenuation} =>
+                CPULight::Point {intensity, color: Vec3::from(color), position: Vec3::from(position),
+                    attenuation_constant: attenuation[0], attenuation_linear: attenuation[1], attenuation_exponential: attenuation[2]},
+            ThreeDLight::Spot {intensity, color, position, direction, cutoff, attenuation} =>
+                CPULight::Spot {intensity, color: Vec3::from(color), position: Vec3::from(position), direction: Vec3::from(direction), cutoff,
+                    attenuation_constant: attenuation[0], attenuation_linear: attenuation[1], attenuation_exponential: attenuation[2]}
+        }).collect();
+        Ok((cameras, lights))
+    }
+
+    fn deserialize_3d(bytes: &[u8]) -> Result<ThreeDMesh, IOError> {
+        if bytes.len() < 2 || bytes[0] != 61 {
+            Err(bincode::Error::new(bincode::ErrorKind::Custom("Corrupt file!".to_string())))?;
+        }
+
+        // The magic number and the version are the first two bytes in all versions of the format
+        let decoded = match bytes[1] {
+            3 => bincode::deserialize::<ThreeDMesh>(bytes)?,
+            4..=255 => Err(IOError::FailedToLoad {message: format!("The .3d file has version {} which is not supported.", bytes[1])})?,
+            _ => {
+                let mut decoded = bincode::deserialize::<ThreeDMeshV2>(bytes)
+                    .or_else(|_| Self::deserialize_version1(bytes))?;
+                if decoded.meshes.len() == 0 {
+                    decoded = Self::deserialize_version1(bytes)?;
+                }
+                decoded.into()
+            }
+        };
+
+        if decoded.meshes.len() == 0 {
+            Err(bincode::Error::new(bincode::ErrorKind::Custom("No mesh data in file!".to_string())))?;
+        }
+        Ok(decoded)
+    }
+
+    fn deserialize_version1(bytes: &[u8]) -> Result<ThreeDMeshV2, bincode::Error> {
+        bincode::deserialize::<ThreeDMeshV1>(bytes).map(|m| ThreeDMeshV2 {
             magic_number: m.magic_number,
             version: 2,
-            meshes: vec![ThreeDMeshSubMesh {
+            meshes: vec![ThreeDMeshSubMeshV2 {
                 indices: if m.indices.len() > 0 { Some(m.indices) } else { None },
                 positions: m.positions,
                 normals: if m.normals.len() > 0 { Some(m.normals) } else { None },
@@ -76,65 +137,131 @@ impl<'a> Loaded<'a> {
 impl Saver {
     ///
     /// Saves the given meshes and materials as a .3d file.
+    /// The texture images of the materials are saved as .png files next to the .3d file.
     ///
     /// # Feature
     /// Only available when the `3d-io` and `image-io` features are enabled.
     ///
     #[cfg(feature = "image-io")]
     pub fn save_3d_file<P: AsRef<Path>>(path: P, cpu_meshes: Vec<CPUMesh>, cpu_materials: Vec<CPUMaterial>) -> Result<(), IOError>
+    {
+        Self::save_3d_scene(path, cpu_meshes, cpu_materials, &[], &[], false)
+    }
+
+    ///
+    /// Saves the given meshes and materials as a single self-contained .3d file.
+    /// The texture images of the materials are encoded as .png and embedded in the .3d file.
+    ///
+    /// # Feature
+    /// Only available when the `3d-io` and `image-io` features are enabled.
+    ///
+    #[cfg(feature = "image-io")]
+    pub fn save_3d_file_embedded<P: AsRef<Path>>(path: P, cpu_meshes: Vec<CPUMesh>, cpu_materials: Vec<CPUMaterial>) -> Result<(), IOError>
+    {
+        Self::save_3d_scene(path, cpu_meshes, cpu_materials, &[], &[], true)
+    }
+
+    ///
+    /// Saves the given meshes, materials, cameras and lights as a .3d file.
+    /// If **embed_textures** is true, the texture images of the materials are encoded as .png and embedded in the .3d file,
+    /// otherwise they are saved as .png files next to the .3d file.
+    ///
+    /// # Feature
+    /// Only available when the `3d-io` and `image-io` features are enabled.
+    ///
+    #[cfg(feature = "image-io")]
+    pub fn save_3d_scene<P: AsRef<Path>>(path: P, cpu_meshes: Vec<CPUMesh>, cpu_materials: Vec<CPUMaterial>,
+                                          cpu_cameras: &[CPUCamera], cpu_lights: &[CPULight], embed_textures: bool) -> Result<(), IOError>
     {
         let dir = path.as_ref().parent().unwrap();
         let filename = path.as_ref().file_stem().unwrap().to_str().unwrap();
-        for cpu_material in cpu_materials.iter() {
-            if let Some(ref cpu_texture) = cpu_material.texture_image {
-                let number_of_channels = cpu_texture.data.len() / (cpu_texture.width * cpu_texture.height);
-                let format = match number_of_channels {
-                    1 => Ok(image::ColorType::L8),
-                    3 => Ok(image::ColorType::Rgb8),
-                    4 => Ok(image::ColorType::Rgba8),
-                    _ => Err(IOError::FailedToSave {message: format!("Texture image could not be saved")})
-                }?;
-                let tex_path = dir.join(format!("{}_{}.png", filename, cpu_material.name));
-                image::save_buffer(tex_path,&cpu_texture.data, cpu_texture.width as u32, cpu_texture.height as u32, format)?;
+        let bytes = Self::serialize(cpu_meshes, cpu_materials, cpu_cameras, cpu_lights, |material_name, suffix, cpu_texture| {
+            let png = png_from_texture(cpu_texture)?;
+            if embed_textures {
+                Ok(ThreeDTexture::Embedded(png))
+            } else {
+                let texture_name = format!("{}_{}{}.png", filename, material_name, suffix);
+                Self::save_file(dir.join(&texture_name), &png)?;
+                Ok(ThreeDTexture::Path(texture_name))
             }
-        }
-        let bytes = Self::serialize(filename, cpu_meshes, cpu_materials)?;
+        })?;
         Self::save_file(dir.join(format!("{}.3d", filename)), &bytes)?;
         Ok(())
     }
 
-    fn serialize(filename: &str, cpu_meshes: Vec<CPUMesh>, cpu_materials: Vec<CPUMaterial>) -> Result<Vec<u8>, IOError>
+    fn serialize<F>(cpu_meshes: Vec<CPUMesh>, cpu_materials: Vec<CPUMaterial>, cpu_cameras: &[CPUCamera], cpu_lights: &[CPULight],
+                    save_texture: F) -> Result<Vec<u8>, IOError>
+        where F: Fn(&str, &str, &CPUTexture<u8>) -> Result<ThreeDTexture, IOError>
     {
         let mut meshes = Vec::new();
         for cpu_mesh in cpu_meshes {
             meshes.push(ThreeDMeshSubMesh {
                     name: cpu_mesh.name,
                     material_name: cpu_mesh.material_name,
+                    primitive_type: cpu_mesh.primitive_type.into(),
                     indices: cpu_mesh.indices,
                     positions: cpu_mesh.positions,
                     normals: cpu_mesh.normals,
-                    uvs: cpu_mesh.uvs
+                    tangents: cpu_mesh.tangents,
+                    uvs: cpu_mesh.uvs,
+                    colors: cpu_mesh.colors
                 });
         }
 
         let mut materials = Vec::new();
         for cpu_material in cpu_materials {
-            let texture_path = cpu_material.texture_image.as_ref().map(|_| format!("{}_{}.png", filename, cpu_material.name));
+            let to_texture = |suffix: &str, cpu_texture: &Option<Rc<CPUTexture<u8>>>| -> Result<Option<ThreeDTexture>, IOError> {
+                cpu_texture.as_ref().map(|cpu_texture| save_texture(&cpu_material.name, suffix, cpu_texture)).transpose()
+            };
+            let texture = to_texture("", &cpu_material.texture_image)?;
+            let specular_texture = to_texture("_specular", &cpu_material.specular_image)?;
+            let bump_texture = to_texture("_bump", &cpu_material.bump_image)?;
+            let opacity_texture = to_texture("_opacity", &cpu_material.opacity_image)?;
             materials.push(ThreeDMaterial {
                     name: cpu_material.name,
-                    texture_path,
                     color: cpu_material.color,
                     diffuse_intensity: cpu_material.diffuse_intensity,
                     specular_intensity: cpu_material.specular_intensity,
-                    specular_power: cpu_material.specular_power
+                    specular_power: cpu_material.specular_power,
+                    emissive_color: cpu_material.emissive_color,
+                    texture,
+                    specular_texture,
+                    bump_texture,
+                    opacity_texture
                 });
         }
 
+        let cameras = cpu_cameras.iter().map(|camera| ThreeDCamera {
+            name: camera.name.clone(),
+            position: camera.position.into(),
+            target: camera.target.into(),
+            up: camera.up.into(),
+            projection: match camera.projection {
+                CPUProjection::Perspective {field_of_view_y, z_near, z_far} =>
+                    ThreeDProjection::Perspective {field_of_view_y: field_of_view_y.0, z_near, z_far},
+                CPUProjection::Orthographic {width, height, depth} => ThreeDProjection::Orthographic {width, height, depth}
+            }
+        }).collect();
+
+        let lights = cpu_lights.iter().map(|light| match *light {
+            CPULight::Ambient {intensity, color} => ThreeDLight::Ambient {intensity, color: color.into()},
+            CPULight::Directional {intensity, color, direction} =>
+                ThreeDLight::Directional {intensity, color: color.into(), direction: direction.into()},
+            CPULight::Point {intensity, color, position, attenuation_constant, attenuation_linear, attenuation_exponential} =>
+                ThreeDLight::Point {intensity, color: color.into(), position: position.into(),
+                    attenuation: [attenuation_constant, attenuation_linear, attenuation_exponential]},
+            CPULight::Spot {intensity, color, position, direction, cutoff, attenuation_constant, attenuation_linear, attenuation_exponential} =>
+                ThreeDLight::Spot {intensity, color: color.into(), position: position.into(), direction: direction.into(), cutoff,
+                    attenuation: [attenuation_constant, attenuation_linear, attenuation_exponential]}
+        }).collect();
+
         Ok(bincode::serialize::<ThreeDMesh>(&ThreeDMesh {
             magic_number: 61,
-            version: 2,
+            version: 3,
             meshes,
-            materials
+            materials,
+            cameras,
+            lights
         })?)
     }
 }
@@ -144,7 +271,9 @@ struct ThreeDMesh {
     pub magic_number: u8,
     pub version: u8,
     pub meshes: Vec<ThreeDMeshSubMesh>,
-    pub materials: Vec<ThreeDMaterial>
+    pub materials: Vec<ThreeDMaterial>,
+    pub cameras: Vec<ThreeDCamera>,
+    pub lights: Vec<ThreeDLight>
 }
 
 #[derive(serde::Serialize, serde::Deserialize, Debug, Default)]
@@ -154,11 +283,110 @@ struct ThreeDMeshSubMesh {
     pub indices: Option<Vec<u32>>,
     pub positions: Vec<f32>,
     pub normals: Option<Vec<f32>>,
+    pub tangents: Option<Vec<f32>>,
     pub uvs: Option<Vec<f32>>,
+    pub colors: Option<Vec<u8>>,
+    pub primitive_type: ThreeDPrimitiveType
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy)]
+enum ThreeDPrimitiveType {
+    Triangles,
+    Lines,
+    Points
+}
+
+impl Default for ThreeDPrimitiveType {
+    fn default() -> Self {
+        ThreeDPrimitiveType::Triangles
+    }
+}
+
+impl From<PrimitiveType> for ThreeDPrimitiveType {
+    fn from(primitive_type: PrimitiveType) -> Self {
+        match primitive_type {
+            PrimitiveType::Triangles => ThreeDPrimitiveType::Triangles,
+            PrimitiveType::Lines => ThreeDPrimitiveType::Lines,
+            PrimitiveType::Points => ThreeDPrimitiveType::Points
+        }
+    }
+}
+
+impl From<ThreeDPrimitiveType> for PrimitiveType {
+    fn from(primitive_type: ThreeDPrimitiveType) -> Self {
+        match primitive_type {
+            ThreeDPrimitiveType::Triangles => PrimitiveType::Triangles,
+            ThreeDPrimitiveType::Lines => PrimitiveType::Lines,
+            ThreeDPrimitiveType::Points => PrimitiveType::Points
+        }
+    }
 }
 
 #[derive(serde::Serialize, serde::Deserialize, Debug, Default)]
 struct ThreeDMaterial {
+    pub name: String,
+    pub color: Option<(f32, f32, f32, f32)>,
+    pub diffuse_intensity: Option<f32>,
+    pub specular_intensity: Option<f32>,
+    pub specular_power: Option<f32>,
+    pub emissive_color: Option<(f32, f32, f32)>,
+    pub texture: Option<ThreeDTexture>,
+    pub specular_texture: Option<ThreeDTexture>,
+    pub bump_texture: Option<ThreeDTexture>,
+    pub opacity_texture: Option<ThreeDTexture>
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+enum ThreeDTexture {
+    // The path of an image file relative to the .3d file
+    Path(String),
+    // The bytes of an encoded image file
+    Embedded(Vec<u8>)
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+struct ThreeDCamera {
+    pub name: String,
+    pub position: [f32; 3],
+    pub target: [f32; 3],
+    pub up: [f32; 3],
+    pub projection: ThreeDProjection
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+enum ThreeDProjection {
+    Perspective {field_of_view_y: f32, z_near: f32, z_far: f32},
+    Orthographic {width: f32, height: f32, depth: f32}
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+enum ThreeDLight {
+    Ambient {intensity: f32, color: [f32; 3]},
+    Directional {intensity: f32, color: [f32; 3], direction: [f32; 3]},
+    Point {intensity: f32, color: [f32; 3], position: [f32; 3], attenuation: [f32; 3]},
+    Spot {intensity: f32, color: [f32; 3], position: [f32; 3], direction: [f32; 3], cutoff: f32, attenuation: [f32; 3]}
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+struct ThreeDMeshV2 {
+    pub magic_number: u8,
+    pub version: u8,
+    pub meshes: Vec<ThreeDMeshSubMeshV2>,
+    pub materials: Vec<ThreeDMaterialV2>
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Default)]
+struct ThreeDMeshSubMeshV2 {
+    pub name: String,
+    pub material_name: Option<String>,
+    pub indices: Option<Vec<u32>>,
+    pub positions: Vec<f32>,
+    pub normals: Option<Vec<f32>>,
+    pub uvs: Option<Vec<f32>>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Default)]
+struct ThreeDMaterialV2 {
     pub name: String,
     pub texture_path: Option<String>,
     pub color: Option<(f32, f32, f32, f32)>,
@@ -167,6 +395,35 @@ struct ThreeDMaterial {
     pub specular_power: Option<f32>
 }
 
+impl From<ThreeDMeshV2> for ThreeDMesh {
+    fn from(mesh: ThreeDMeshV2) -> Self {
+        ThreeDMesh {
+            magic_number: mesh.magic_number,
+            version: 3,
+            meshes: mesh.meshes.into_iter().map(|m| ThreeDMeshSubMesh {
+                name: m.name,
+                material_name: m.material_name,
+                indices: m.indices,
+                positions: m.positions,
+                normals: m.normals,
+                uvs: m.uvs,
+                ..Default::default()
+            }).collect(),
+            materials: mesh.materials.into_iter().map(|m| ThreeDMaterial {
+                name: m.name,
+                color: m.color,
+                diffuse_intensity: m.diffuse_intensity,
+                specular_intensity: m.specular_intensity,
+                specular_power: m.specular_power,
+                texture: m.texture_path.map(ThreeDTexture::Path),
+                ..Default::default()
+            }).collect(),
+            cameras: vec![],
+            lights: vec![]
+        }
+    }
+}
+
 #[derive(serde::Serialize, serde::Deserialize, Debug)]
 struct ThreeDMeshV1 {
     pub magic_number: u8,
@@ -174,4 +431,236 @@ struct ThreeDMeshV1 {
     pub indices: Vec<u32>,
     pub positions: Vec<f32>,
     pub normals: Vec<f32>
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    // A triangle saved in version 1 of the format, encoded with bincode
+    const VERSION_1: &[u8] = &[
+        61, 1, // magic number and version
+        3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, // indices
+        9, 0, 0, 0, 0, 0, 0, 0, // 9 positions
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, // position 0
+        0, 0, 128, 63, 0, 0, 0, 0, 0, 0, 0, 0, // position 1
+        0, 0, 0, 0, 0, 0, 128, 63, 0, 0, 0, 0, // position 2
+        9, 0, 0, 0, 0, 0, 0, 0, // 9 normals
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 128, 63, // normal 0
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 128, 63, // normal 1
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 128, 63, // normal 2
+    ];
+
+    // A triangle with uv coordinates and a material saved in version 2 of the format, encoded with bincode
+    const VERSION_2: &[u8] = &[
+        61, 2, // magic number and version
+        1, 0, 0, 0, 0, 0, 0, 0, // 1 mesh
+        8, 0, 0, 0, 0, 0, 0, 0, 116, 114, 105, 97, 110, 103, 108, 101, // name
+        1, 3, 0, 0, 0, 0, 0, 0, 0, 114, 101, 100, // material name
+        1, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, // indices
+        9, 0, 0, 0, 0, 0, 0, 0, // 9 positions
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, // position 0
+        0, 0, 128, 63, 0, 0, 0, 0, 0, 0, 0, 0, // position 1
+        0, 0, 0, 0, 0, 0, 128, 63, 0, 0, 0, 0, // position 2
+        0, // no normals
+        1, 6, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, // 6 uvs, uv 0
+        0, 0, 128, 63, 0, 0, 0, 0, // uv 1
+        0, 0, 0, 0, 0, 0, 128, 63, // uv 2
+        1, 0, 0, 0, 0, 0, 0, 0, // 1 material
+        3, 0, 0, 0, 0, 0, 0, 0, 114, 101, 100, // name
+        0, // no texture path
+        1, 0, 0, 128, 63, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 128, 63, // color
+        1, 0, 0, 0, 63, // diffuse intensity
+        0, // no specular intensity
+        1, 0, 0, 160, 64, // specular power
+    ];
+
+
+    fn load(bytes: &[u8]) -> Loaded {
+        let mut loaded = HashMap::new();
+        loaded.insert(std::path::PathBuf::from("model.3d"), Ok(bytes.to_vec()));
+        Loaded::new(loaded)
+    }
+
+    #[test]
+    fn load_version_1() {
+        let loaded = load(VERSION_1);
+        let (cpu_meshes, cpu_materials) = loaded.three_d("model.3d").unwrap();
+        assert_eq!(cpu_meshes.len(), 1);
+        assert!(cpu_materials.is_empty());
+        assert_eq!(cpu_meshes[0].indices, Some(vec![0, 1, 2]));
+        assert_eq!(cpu_meshes[0].positions, vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0]);
+        assert_eq!(cpu_meshes[0].normals, Some([0.0, 0.0, 1.0].repeat(3)));
+        assert_eq!(cpu_meshes[0].uvs, None);
+        let (cpu_cameras, cpu_lights) = loaded.three_d_cameras_and_lights("model.3d").unwrap();
+        assert!(cpu_cameras.is_empty() && cpu_lights.is_empty());
+    }
+
+    #[test]
+    fn load_version_2() {
+        let (cpu_meshes, cpu_materials) = load(VERSION_2).three_d("model.3d").unwrap();
+        assert_eq!(cpu_meshes.len(), 1);
+        assert_eq!(cpu_meshes[0].name, "triangle");
+        assert_eq!(cpu_meshes[0].material_name, Some("red".to_string()));
+        assert_eq!(cpu_meshes[0].indices, Some(vec![0, 1, 2]));
+        assert_eq!(cpu_meshes[0].positions, vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0]);
+        assert_eq!(cpu_meshes[0].normals, None);
+        assert_eq!(cpu_meshes[0].uvs, Some(vec![0.0, 0.0, 1.0, 0.0, 0.0, 1.0]));
+        assert_eq!(cpu_materials.len(), 1);
+        assert_eq!(cpu_materials[0].name, "red");
+        assert_eq!(cpu_materials[0].color, Some((1.0, 0.0, 0.0, 1.0)));
+        assert_eq!(cpu_materials[0].diffuse_intensity, Some(0.5));
+        assert_eq!(cpu_materials[0].specular_intensity, None);
+        assert_eq!(cpu_materials[0].specular_power, Some(5.0));
+        assert!(cpu_materials[0].texture_image.is_none());
+    }
+
+    #[test]
+    fn load_invalid() {
+        assert!(load(&[60, 3]).three_d("model.3d").is_err());
+        match load(&[61, 4, 0, 0]).three_d("model.3d") {
+            Err(IOError::FailedToLoad {message}) => assert!(message.contains("version 4")),
+            result => panic!("Expected an unsupported version, got {:?}", result.map(|_| ()))
+        }
+    }
+
+    fn test_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("three-d-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn load_dir(dir: &Path) -> Loaded {
+        let mut loaded = HashMap::new();
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            loaded.insert(path.clone(), Ok(std::fs::read(&path).unwrap()));
+        }
+        Loaded::new(loaded)
+    }
+
+    fn meshes() -> Vec<CPUMesh> {
+        vec![
+            CPUMesh {
+                name: "quad".to_string(),
+                material_name: Some("textured".to_string()),
+                positions: vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 1.0, 0.0],
+                indices: Some(vec![0, 1, 2, 0, 2, 3]),
+                normals: Some([0.0, 0.0, 1.0].repeat(4)),
+                tangents: Some([1.0, 0.0, 0.0, 1.0].repeat(4)),
+                uvs: Some(vec![0.0, 0.0, 1.0, 0.0, 1.0, 1.0, 0.0, 1.0]),
+                colors: Some(vec![255, 0, 0, 255, 0, 255, 0, 255, 0, 0, 255, 255, 255, 255, 255, 128]),
+                ..Default::default()
+            },
+            CPUMesh {
+                name: "edges".to_string(),
+                primitive_type: PrimitiveType::Lines,
+                positions: vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0],
+                indices: Some(vec![0, 1]),
+                ..Default::default()
+            }
+        ]
+    }
+
+    fn materials() -> Vec<CPUMaterial> {
+        let texture = Rc::new(CPUTexture {data: vec![255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255], width: 2, height: 2,
+            format: Format::RGB8, ..Default::default()});
+        let normal_map = Rc::new(CPUTexture {data: vec![128, 128, 255], width: 1, height: 1, format: Format::RGB8, ..Default::default()});
+        vec![CPUMaterial {name: "textured".to_string(), color: Some((1.0, 1.0, 1.0, 0.5)), emissive_color: Some((0.5, 0.25, 0.0)),
+            texture_image: Some(texture), bump_image: Some(normal_map), ..Default::default()}]
+    }
+
+    fn cameras() -> Vec<CPUCamera> {
+        vec![
+            CPUCamera {name: "perspective".to_string(), position: vec3(0.0, 1.0, 5.0), target: vec3(0.0, 0.0, 0.0), up: vec3(0.0, 1.0, 0.0),
+                projection: CPUProjection::Perspective {field_of_view_y: degrees(45.0), z_near: 0.1, z_far: 100.0}},
+            CPUCamera {name: "orthographic".to_string(), position: vec3(0.0, 5.0, 0.0), target: vec3(0.0, 0.0, 0.0), up: vec3(0.0, 0.0, -1.0),
+                projection: CPUProjection::Orthographic {width: 10.0, height: 5.0, depth: 20.0}}
+        ]
+    }
+
+    fn lights() -> Vec<CPULight> {
+        vec![
+            CPULight::Ambient {intensity: 0.2, color: vec3(1.0, 1.0, 1.0)},
+            CPULight::Directional {intensity: 0.5, color: vec3(1.0, 0.9, 0.8), direction: vec3(0.0, -1.0, 0.0)},
+            CPULight::Point {intensity: 0.8, color: vec3(0.0, 1.0, 0.0), position: vec3(1.0, 2.0, 3.0),
+                attenuation_constant: 1.0, attenuation_linear: 0.1, attenuation_exponential: 0.01},
+            CPULight::Spot {intensity: 1.0, color: vec3(0.0, 0.0, 1.0), position: vec3(0.0, 5.0, 0.0), direction: vec3(0.0, -1.0, 0.0), cutoff: 30.0,
+                attenuation_constant: 1.0, attenuation_linear: 0.0, attenuation_exponential: 0.0}
+        ]
+    }
+
+    fn check_round_trip(cpu_meshes: &[CPUMesh], cpu_materials: &[CPUMaterial]) {
+        let expected = meshes();
+        assert_eq!(cpu_meshes.len(), expected.len());
+        for (cpu_mesh, expected) in cpu_meshes.iter().zip(expected.iter()) {
+            assert_eq!(cpu_mesh.name, expected.name);
+            assert_eq!(cpu_mesh.material_name, expected.material_name);
+            assert_eq!(cpu_mesh.primitive_type, expected.primitive_type);
+            assert_eq!(cpu_mesh.positions, expected.positions);
+            assert_eq!(cpu_mesh.indices, expected.indices);
+            assert_eq!(cpu_mesh.normals, expected.normals);
+            assert_eq!(cpu_mesh.tangents, expected.tangents);
+            assert_eq!(cpu_mesh.uvs, expected.uvs);
+            assert_eq!(cpu_mesh.colors, expected.colors);
+        }
+
+        let expected = materials();
+        assert_eq!(cpu_materials.len(), 1);
+        assert_eq!(cpu_materials[0].name, expected[0].name);
+        assert_eq!(cpu_materials[0].color, expected[0].color);
+        assert_eq!(cpu_materials[0].emissive_color, expected[0].emissive_color);
+        assert_eq!(cpu_materials[0].texture_image.as_ref().unwrap().data, expected[0].texture_image.as_ref().unwrap().data);
+        assert_eq!(cpu_materials[0].bump_image.as_ref().unwrap().data, expected[0].bump_image.as_ref().unwrap().data);
+        assert!(cpu_materials[0].specular_image.is_none());
+    }
+
+    #[test]
+    fn save_and_load_scene_with_embedded_textures() {
+        let dir = test_dir("3d-embedded");
+        Saver::save_3d_scene(dir.join("scene.3d"), meshes(), materials(), &cameras(), &lights(), true).unwrap();
+
+        // The textures are embedded, so only the .3d file is saved
+        let files: Vec<_> = std::fs::read_dir(&dir).unwrap().map(|entry| entry.unwrap().file_name()).collect();
+        assert_eq!(files, vec!["scene.3d"]);
+        let bytes = std::fs::read(dir.join("scene.3d")).unwrap();
+        assert_eq!(&bytes[..2], &[61, 3]);
+
+        let loaded = load_dir(&dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+        let (cpu_meshes, cpu_materials) = loaded.three_d(dir.join("scene.3d")).unwrap();
+        check_round_trip(&cpu_meshes, &cpu_materials);
+        let (cpu_cameras, cpu_lights) = loaded.three_d_cameras_and_lights(dir.join("scene.3d")).unwrap();
+        assert_eq!(cpu_cameras, cameras());
+        assert_eq!(cpu_lights, lights());
+    }
+
+    #[test]
+    fn save_and_load_with_texture_files() {
+        let dir = test_dir("3d-files");
+        Saver::save_3d_file(dir.join("model.3d"), meshes(), materials()).unwrap();
+
+        let mut files: Vec<String> = std::fs::read_dir(&dir).unwrap().map(|entry| entry.unwrap().file_name().into_string().unwrap()).collect();
+        files.sort();
+        assert_eq!(files, vec!["model.3d", "model_textured.png", "model_textured_bump.png"]);
+
+        let loaded = load_dir(&dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+        let (cpu_meshes, cpu_materials) = loaded.three_d(dir.join("model.3d")).unwrap();
+        check_round_trip(&cpu_meshes, &cpu_materials);
+        let (cpu_cameras, cpu_lights) = loaded.three_d_cameras_and_lights(dir.join("model.3d")).unwrap();
+        assert!(cpu_cameras.is_empty() && cpu_lights.is_empty());
+    }
+
+    #[test]
+    fn save_embedded() {
+        let dir = test_dir("3d-file-embedded");
+        Saver::save_3d_file_embedded(dir.join("model.3d"), meshes(), materials()).unwrap();
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        let loaded = load_dir(&dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+        let (cpu_meshes, cpu_materials) = loaded.three_d(dir.join("model.3d")).unwrap();
+        check_round_trip(&cpu_meshes, &cpu_materials);
+    }
+}
@@ -0,0 +1,167 @@
+use crate::io::*;
+use std::path::Path;
+
+impl Loader {
+    ///
+    /// Registers all of the files in the given zip archive as [virtual files](crate::Loader::register_bytes),
+    /// so they are loaded from the archive instead of from disk or the network.
+    /// The unpacked files are kept in memory until they are registered again with the same path.
+    /// The path of each file is the path inside the archive joined to the given mount path, so relative paths between
+    /// the files in the archive (for example from an .obj file to its .mtl file or from an .mtl file to `../textures/a.png`)
+    /// are resolved inside the archive.
+    /// Files in an archive which is itself loaded using the [Loader](crate::Loader) are only part of the resources loaded after it is registered.
+    /// The archive can be embedded using `include_bytes!` or it can itself be loaded using the [Loader](crate::Loader).
+    /// Only stored and deflated files are supported.
+    ///
+    /// # Feature
+    /// Only available when the `zip-io` feature is enabled.
+    ///
+    pub fn register_zip<P: AsRef<Path>>(mount_path: P, zip_bytes: &[u8]) -> Result<(), IOError> {
+        for (name, bytes) in unzip(zip_bytes)? {
+            Self::register_bytes(mount_path.as_ref().join(name), &bytes);
+        }
+        Ok(())
+    }
+}
+
+fn unzip(bytes: &[u8]) -> Result<Vec<(String, Vec<u8>)>, IOError> {
+    let error = |message: &str| IOError::FailedToLoad {message: format!("Could not read zip archive: {}", message)};
+    let u16_at = |offset: usize| -> Result<usize, IOError> {
+        bytes.get(offset..offset + 2).map(|b| u16::from_le_bytes([b[0], b[1]]) as usize).ok_or_else(|| error("Unexpected end of file."))
+    };
+    let u32_at = |offset: usize| -> Result<usize, IOError> {
+        bytes.get(offset..offset + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize).ok_or_else(|| error("Unexpected end of file."))
+    };
+
+    // The end of central directory record is at the end of the archive, possibly followed by a comment of at most 65535 bytes
+    let end_of_directory = (0..bytes.len().saturating_sub(21)).rev().take(65536)
+        .find(|&offset| bytes[offset..offset + 4] == [0x50, 0x4b, 0x05, 0x06])
+        .ok_or_else(|| error("The end of central directory record is missing."))?;
+    let entry_count = u16_at(end_of_directory + 10)?;
+    let mut offset = u32_at(end_of_directory + 16)?;
+    if entry_count == 0xffff || offset == 0xffffffff {
+        Err(error("Zip64 archives are not supported."))?;
+    }
+
+    let mut files = Vec::with_capacity(entry_count);
+    for _ in 0..entry_count {
+        if u32_at(offset)? != 0x02014b50 {
+            Err(error("Invalid central directory entry."))?;
+        }
+        let method = u16_at(offset + 10)?;
+        let compressed_size = u32_at(offset + 20)?;
+        let size = u32_at(offset + 24)?;
+        let name_length = u16_at(offset + 28)?;
+        let extra_length = u16_at(offset + 30)?;
+        let comment_length = u16_at(offset + 32)?;
+        let header_offset = u32_at(offset + 42)?;
+        let name = String::from_utf8_lossy(bytes.get(offset + 46..offset + 46 + name_length)
+            .ok_or_else(|| error("Unexpected end of file."))?).to_string();
+        offset += 46 + name_length + extra_length + comment_length;
+
+        if name.ends_with('/') {
+            continue; // Directory
+        }
+        if u32_at(header_offset)? != 0x04034b50 {
+            Err(error(&format!("Invalid local header for {}.", name)))?;
+        }
+        let data_offset = header_offset + 30 + u16_at(header_offset + 26)? + u16_at(header_offset + 28)?;
+        let data = bytes.get(data_offset..data_offset + compressed_size).ok_or_else(|| error("Unexpected end of file."))?;
+        let data = match method {
+            0 => data.to_vec(),
+            // The size from the central directory limits the memory used by a file which inflates to more than its size, for example a zip bomb.
+            // The output buffer grows by doubling and is not allowed to grow past the limit, so twice the size is needed to fit a file of exactly the right size.
+            8 => miniz_oxide::inflate::decompress_to_vec_with_limit(data, size.saturating_mul(2)).map_err(|e| error(&format!("Could not inflate {}: {:?}", name, e)))?,
+            _ => Err(error(&format!("The compression method {} used for {} is not supported.", method, name)))?
+        };
+        if data.len() != size {
+            Err(error(&format!("The size of {} is {} bytes, but expected {} bytes.", name, data.len(), size)))?;
+        }
+        files.push((name, data));
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Creates a zip archive where the files are stored without compression
+    fn zip(files: &[(&str, &[u8])]) -> Vec<u8> {
+        zip_with_sizes(&files.iter().map(|(name, data)| (*name, data.to_vec(), 0, data.len())).collect::<Vec<_>>())
+    }
+
+    // Creates a zip archive from the file names, file data, compression methods and uncompressed sizes
+    fn zip_with_sizes(files: &[(&str, Vec<u8>, u16, usize)]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let mut directory = Vec::new();
+        for (name, data, method, size) in files.iter() {
+            let header_offset = bytes.len() as u32;
+            let sizes = [(data.len() as u32).to_le_bytes(), (*size as u32).to_le_bytes()].concat();
+            bytes.extend_from_slice(&[0x50, 0x4b, 0x03, 0x04, 20, 0, 0, 0]);
+            bytes.extend_from_slice(&method.to_le_bytes());
+            bytes.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 0]);
+            bytes.extend_from_slice(&sizes);
+            bytes.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            bytes.extend_from_slice(&[0, 0]);
+            bytes.extend_from_slice(name.as_bytes());
+            bytes.extend_from_slice(data);
+
+            directory.extend_from_slice(&[0x50, 0x4b, 0x01, 0x02, 20, 0, 20, 0, 0, 0]);
+            directory.extend_from_slice(&method.to_le_bytes());
+            directory.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 0]);
+            directory.extend_from_slice(&sizes);
+            directory.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            directory.extend_from_slice(&[0; 12]);
+            directory.extend_from_slice(&header_offset.to_le_bytes());
+            directory.extend_from_slice(name.as_bytes());
+        }
+        let directory_offset = bytes.len() as u32;
+        bytes.extend_from_slice(&directory);
+        bytes.extend_from_slice(&[0x50, 0x4b, 0x05, 0x06, 0, 0, 0, 0]);
+        bytes.extend_from_slice(&(files.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(&(files.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&directory_offset.to_le_bytes());
+        bytes.extend_from_slice(&[0, 0]);
+        bytes
+    }
+
+    #[test]
+    fn register_zip() {
+        let archive = zip(&[("models/cube.mtl", b"map_Kd ../textures/cube.png"), ("textures/", b""), ("textures/cube.png", b"png")]);
+        Loader::register_zip("assets", &archive).unwrap();
+        let loaded = Loaded::new(std::collections::HashMap::new());
+        assert_eq!(loaded.bytes("assets/models/cube.mtl").unwrap(), b"map_Kd ../textures/cube.png");
+        // Relative paths between files in the archive are resolved
+        assert_eq!(loaded.bytes(Path::new("assets/models").join("../textures/cube.png")).unwrap(), b"png");
+        assert_eq!(loaded.bytes("./assets/textures/cube.png").unwrap(), b"png");
+        assert!(loaded.bytes("assets/models/cube.png").is_err());
+
+        // Registering the archive again replaces the files
+        Loader::register_zip("assets", &zip(&[("textures/cube.png", b"new")])).unwrap();
+        assert_eq!(loaded.bytes("assets/textures/cube.png").unwrap(), b"png");
+        assert_eq!(Loaded::new(std::collections::HashMap::new()).bytes("assets/textures/cube.png").unwrap(), b"new");
+    }
+
+    #[test]
+    fn register_deflated_zip() {
+        let data = "v 0 0 0\n".repeat(100);
+        let compressed = miniz_oxide::deflate::compress_to_vec(data.as_bytes(), 6);
+        assert!(compressed.len() < data.len());
+        Loader::register_zip("deflated", &zip_with_sizes(&[("model.obj", compressed.clone(), 8, data.len())])).unwrap();
+        assert_eq!(Loaded::new(std::collections::HashMap::new()).bytes("deflated/model.obj").unwrap(), data.as_bytes());
+
+        // A file which inflates to more than the size in the central directory is rejected, and so is a file which inflates to less
+        assert!(Loader::register_zip("deflated", &zip_with_sizes(&[("bomb.obj", compressed.clone(), 8, 100)])).is_err());
+        assert!(Loader::register_zip("deflated", &zip_with_sizes(&[("short.obj", compressed, 8, data.len() + 1)])).is_err());
+    }
+
+    #[test]
+    fn invalid_zip() {
+        assert!(Loader::register_zip("assets", b"not a zip archive").is_err());
+        let mut archive = zip(&[("model.obj", b"v 0 0 0")]);
+        archive.drain(30..35);
+        assert!(Loader::register_zip("assets", &archive).is_err());
+    }
+}
@@ -88,7 +88,7 @@ impl std::ops::Deref for InstancedMeshProgram {
 }
 
 ///
-/// Similar to [Mesh](crate::Mesh), except it is possible to render many instances of the same triangle mesh efficiently.
+/// Similar to [Mesh](crate::Mesh), except it is possible to render many instances of the same mesh efficiently.
 ///
 pub struct InstancedMesh {
     context: Context,
@@ -96,6 +96,7 @@ pub struct InstancedMesh {
     normal_buffer: Option<VertexBuffer>,
     index_buffer: Option<ElementBuffer>,
     uv_buffer: Option<VertexBuffer>,
+    primitive_type: PrimitiveType,
     instance_count: u32,
     instance_buffer1: VertexBuffer,
     instance_buffer2: VertexBuffer,
@@ -110,15 +111,19 @@ impl InstancedMesh
     /// Each instance is transformed with the given transformation before it is rendered.
     /// The transformations can be updated by the [update_transformations](Self::update_transformations) function.
     ///
+    /// # Errors
+    /// Will return an error containing the [validation report](crate::CPUMesh::validate) if the mesh is not valid.
+    ///
     pub fn new(context: &Context, transformations: &[Mat4], cpu_mesh: &CPUMesh) -> Result<Self, Error>
     {
+        crate::object::mesh::validate(cpu_mesh)?;
         let position_buffer = VertexBuffer::new_with_static_f32(context, &cpu_mesh.positions)?;
         let normal_buffer = if let Some(ref normals) = cpu_mesh.normals { Some(VertexBuffer::new_with_static_f32(context, normals)?) } else {None};
         let index_buffer = if let Some(ref ind) = cpu_mesh.indices { Some(ElementBuffer::new_with_u32(context, ind)?) } else {None};
         let uv_buffer = if let Some(ref uvs) = cpu_mesh.uvs { Some(VertexBuffer::new_with_static_f32(context, uvs)?) } else {None};
 
         let mut mesh = Self { context: context.clone(), instance_count: 0,
-            position_buffer, normal_buffer, index_buffer, uv_buffer,
+            position_buffer, normal_buffer, index_buffer, uv_buffer, primitive_type: cpu_mesh.primitive_type,
             instance_buffer1: VertexBuffer::new_with_dynamic_f32(context, &[])?,
             instance_buffer2: VertexBuffer::new_with_dynamic_f32(context, &[])?,
             instance_buffer3: VertexBuffer::new_with_dynamic_f32(context, &[])?
@@ -253,20 +258,20 @@ impl InstancedMesh
         program.use_attribute_vec3(&self.position_buffer, "position")?;
         if program.use_uvs {
             let uv_buffer = self.uv_buffer.as_ref().ok_or(
-                Error::FailedToCreateMesh {message: "The mesh shader program needs uv coordinates, but the mesh does not have any.".to_string()})?;
+                Error::FailedToCreateMesh {message: "The mesh shader program needs uv coordinates, but the mesh does not have any.".to_string(), report: None})?;
             program.use_attribute_vec2(uv_buffer, "uv_coordinates")?;
         }
         if program.use_normals {
             let normal_buffer = self.normal_buffer.as_ref().ok_or(
-                Error::FailedToCreateMesh {message: "The mesh shader program needs normals, but the mesh does not have any. Consider calculating the normals on the CPUMesh.".to_string()})?;
+                Error::FailedToCreateMesh {message: "The mesh shader program needs normals, but the mesh does not have any. Consider calculating the normals on the CPUMesh.".to_string(), report: None})?;
             program.use_uniform_mat4("normalMatrix", &transformation.invert().unwrap().transpose())?;
             program.use_attribute_vec3(normal_buffer, "normal")?;
         }
 
         if let Some(ref index_buffer) = self.index_buffer {
-            program.draw_elements_instanced(render_states, viewport,index_buffer, self.instance_count);
+            program.draw_primitive_elements_instanced(self.primitive_type, render_states, viewport, index_buffer, self.instance_count);
         } else {
-            program.draw_arrays_instanced(render_states, viewport,self.position_buffer.count() as u32/3, self.instance_count);
+            program.draw_primitive_arrays_instanced(self.primitive_type, render_states, viewport, self.position_buffer.count() as u32/3, self.instance_count);
         }
         Ok(())
     }
@@ -88,7 +88,8 @@ impl std::ops::Deref for MeshProgram {
 }
 
 ///
-/// A triangle mesh which can be rendered with one of the default render functions or with a custom [MeshProgram](MeshProgram).
+/// A mesh which can be rendered with one of the default render functions or with a custom [MeshProgram](MeshProgram).
+/// It is usually a triangle mesh, but it can also consist of lines or points, see [PrimitiveType](crate::PrimitiveType).
 /// See also [PhongForwardMesh](crate::PhongForwardMesh) and [PhongDeferredMesh](crate::PhongDeferredMesh) for rendering a mesh with lighting.
 ///
 pub struct Mesh {
@@ -98,6 +99,7 @@ pub struct Mesh {
     index_buffer: Option<ElementBuffer>,
     uv_buffer: Option<VertexBuffer>,
     color_buffer: Option<VertexBuffer>,
+    primitive_type: PrimitiveType,
 }
 
 impl Mesh {
@@ -105,8 +107,13 @@ impl Mesh {
     /// Copies the per vertex data defined in the given [CPUMesh](crate::CPUMesh) to the GPU, thereby
     /// making it possible to render the mesh.
     ///
+    /// # Errors
+    /// Will return an error containing the [validation report](crate::CPUMesh::validate) if the mesh is not valid,
+    /// in which case [repair](crate::CPUMesh::repair) might help.
+    ///
     pub fn new(context: &Context, cpu_mesh: &CPUMesh) -> Result<Self, Error>
     {
+        validate(cpu_mesh)?;
         let position_buffer = VertexBuffer::new_with_static_f32(context, &cpu_mesh.positions)?;
         let normal_buffer = if let Some(ref normals) = cpu_mesh.normals { Some(VertexBuffer::new_with_static_f32(context, normals)?) } else {None};
         let index_buffer = if let Some(ref ind) = cpu_mesh.indices { Some(ElementBuffer::new_with_u32(context, ind)?) } else {None};
@@ -115,7 +122,7 @@ impl Mesh {
         unsafe {
             MESH_COUNT += 1;
         }
-        Ok(Mesh {context: context.clone(), position_buffer, normal_buffer, index_buffer, uv_buffer, color_buffer})
+        Ok(Mesh {context: context.clone(), position_buffer, normal_buffer, index_buffer, uv_buffer, color_buffer, primitive_type: cpu_mesh.primitive_type})
     }
 
     ///
@@ -237,25 +244,25 @@ impl Mesh {
         program.use_attribute_vec3(&self.position_buffer, "position")?;
         if program.use_uvs {
             let uv_buffer = self.uv_buffer.as_ref().ok_or(
-                Error::FailedToCreateMesh {message: "The mesh shader program needs uv coordinates, but the mesh does not have any.".to_string()})?;
+                Error::FailedToCreateMesh {message: "The mesh shader program needs uv coordinates, but the mesh does not have any.".to_string(), report: None})?;
             program.use_attribute_vec2(uv_buffer, "uv_coordinates")?;
         }
         if program.use_normals {
             let normal_buffer = self.normal_buffer.as_ref().ok_or(
-                Error::FailedToCreateMesh {message: "The mesh shader program needs normals, but the mesh does not have any. Consider calculating the normals on the CPUMesh.".to_string()})?;
+                Error::FailedToCreateMesh {message: "The mesh shader program needs normals, but the mesh does not have any. Consider calculating the normals on the CPUMesh.".to_string(), report: None})?;
             program.use_uniform_mat4("normalMatrix", &transformation.invert().unwrap().transpose())?;
             program.use_attribute_vec3(normal_buffer, "normal")?;
         }
         if program.use_colors {
             let color_buffer = self.color_buffer.as_ref().ok_or(
-                Error::FailedToCreateMesh {message: "The mesh shader program needs per vertex colors, but the mesh does not have any.".to_string()})?;
+                Error::FailedToCreateMesh {message: "The mesh shader program needs per vertex colors, but the mesh does not have any.".to_string(), report: None})?;
             program.use_attribute_vec4(color_buffer, "color")?;
         }
 
         if let Some(ref index_buffer) = self.index_buffer {
-            program.draw_elements(render_states, viewport,index_buffer);
+            program.draw_primitive_elements(self.primitive_type, render_states, viewport, index_buffer);
         } else {
-            program.draw_arrays(render_states, viewport,self.position_buffer.count() as u32/3);
+            program.draw_primitive_arrays(self.primitive_type, render_states, viewport, self.position_buffer.count() as u32/3);
         }
         Ok(())
     }
@@ -280,4 +287,11 @@ static mut PROGRAM_COLOR: Option<MeshProgram> = None;
 static mut PROGRAM_TEXTURE: Option<MeshProgram> = None;
 static mut PROGRAM_DEPTH: Option<MeshProgram> = None;
 static mut PROGRAM_PER_VERTEX_COLOR: Option<MeshProgram> = None;
-static mut MESH_COUNT: u32 = 0;
\ No newline at end of file
+static mut MESH_COUNT: u32 = 0;
+
+pub(crate) fn validate(cpu_mesh: &CPUMesh) -> Result<(), Error> {
+    if !cpu_mesh.is_valid() {
+        Err(Error::FailedToCreateMesh {message: format!("The mesh {} is not valid.", cpu_mesh.name), report: Some(cpu_mesh.validate())})?;
+    }
+    Ok(())
+}
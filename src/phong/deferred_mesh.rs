@@ -24,7 +24,7 @@ impl PhongDeferredMesh {
     {
         if cpu_mesh.normals.is_none() {
             Err(Error::FailedToCreateMesh {message:
-              "Cannot create a mesh without normals. Consider calling compute_normals on the CPUMesh before creating the mesh.".to_string()})?
+              "Cannot create a mesh without normals. Consider calling compute_normals on the CPUMesh before creating the mesh.".to_string(), report: None})?
         }
         let mesh = Mesh::new(context, cpu_mesh)?;
         unsafe {MESH_COUNT += 1;}
@@ -28,17 +28,40 @@ pub struct PhongMaterial {
 impl PhongMaterial {
     ///
     /// Constructor.
+    /// Materials with the same shared CPU texture, for example loaded using [Loaded::shared_image](crate::Loaded::shared_image),
+    /// also share the GPU texture as long as it is in use.
     ///
     pub fn new(context: &Context, cpu_material: &CPUMaterial) -> Result<Self, Error> {
         let color_source = if let Some(ref cpu_texture) = cpu_material.texture_image {
-            ColorSource::Texture(Rc::new(texture::Texture2D::new_with_u8(&context, cpu_texture)?))
+            ColorSource::Texture(TextureCache::shared_texture(context, cpu_texture)?)
         }
         else {
-            ColorSource::Color(cpu_material.color.map(|(r, g, b, a)| vec4(r, g, b, a)).unwrap_or(vec4(1.0, 1.0, 1.0, 1.0)))
+            Self::color(cpu_material)
         };
-        Ok(Self {name: cpu_material.name.clone(), color_source, diffuse_intensity: cpu_material.diffuse_intensity.unwrap_or(0.5),
+        Ok(Self::with_color_source(cpu_material, color_source))
+    }
+
+    ///
+    /// Constructor which uses the given cache to share the GPU textures between materials with the same shared CPU textures.
+    ///
+    pub fn new_with_cache(context: &Context, cpu_material: &CPUMaterial, cache: &mut TextureCache) -> Result<Self, Error> {
+        let color_source = if let Some(ref cpu_texture) = cpu_material.texture_image {
+            ColorSource::Texture(cache.texture(context, cpu_texture)?)
+        }
+        else {
+            Self::color(cpu_material)
+        };
+        Ok(Self::with_color_source(cpu_material, color_source))
+    }
+
+    fn color(cpu_material: &CPUMaterial) -> ColorSource {
+        ColorSource::Color(cpu_material.color.map(|(r, g, b, a)| vec4(r, g, b, a)).unwrap_or(vec4(1.0, 1.0, 1.0, 1.0)))
+    }
+
+    fn with_color_source(cpu_material: &CPUMaterial, color_source: ColorSource) -> Self {
+        Self {name: cpu_material.name.clone(), color_source, diffuse_intensity: cpu_material.diffuse_intensity.unwrap_or(0.5),
             specular_intensity: cpu_material.specular_intensity.unwrap_or(0.2),
-            specular_power: cpu_material.specular_power.unwrap_or(6.0)})
+            specular_power: cpu_material.specular_power.unwrap_or(6.0)}
     }
 
     pub(crate) fn bind(&self, program: &Program) -> Result<(), Error> {
@@ -13,6 +13,9 @@ pub mod glutin_window;
 #[cfg(all(feature = "glutin-window", not(target_arch = "wasm32")))]
 pub use crate::glutin_window::*;
 
+#[cfg(all(feature = "glutin-window", not(target_arch = "wasm32")))]
+mod recorder;
+
 #[doc(hidden)]
 #[cfg(all(feature = "canvas", target_arch = "wasm32"))]
 pub mod canvas;
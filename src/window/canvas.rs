@@ -14,6 +14,19 @@ pub enum WindowError {
     EventListenerError {message: String}
 }
 
+impl std::fmt::Display for WindowError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WindowError::WindowCreationError {message} => write!(f, "failed to create window: {}", message),
+            WindowError::ContextError {message} => write!(f, "WebGL context error: {}", message),
+            WindowError::PerformanceError {message} => write!(f, "performance error: {}", message),
+            WindowError::EventListenerError {message} => write!(f, "failed to add event listener: {}", message)
+        }
+    }
+}
+
+impl std::error::Error for WindowError {}
+
 pub struct Window
 {
     gl: crate::Context,
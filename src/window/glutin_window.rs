@@ -6,6 +6,8 @@ use glutin::window::WindowBuilder;
 use glutin::ContextBuilder;
 use crate::math::*;
 use crate::frame::*;
+use super::recorder::Recorder;
+use log::error;
 
 ///
 /// Error message from the [window](crate::window) module.
@@ -16,6 +18,24 @@ pub enum WindowError {
     ContextError(glutin::ContextError)
 }
 
+impl std::fmt::Display for WindowError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WindowError::WindowCreationError(e) => write!(f, "failed to create window: {}", e),
+            WindowError::ContextError(e) => write!(f, "OpenGL context error: {}", e)
+        }
+    }
+}
+
+impl std::error::Error for WindowError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            WindowError::WindowCreationError(e) => Some(e),
+            WindowError::ContextError(e) => Some(e)
+        }
+    }
+}
+
 impl From<glutin::CreationError> for WindowError {
     fn from(other: glutin::CreationError) -> Self {
         WindowError::WindowCreationError(other)
@@ -93,6 +113,9 @@ impl Window
         let windowed_context = self.windowed_context;
         let mut last_time = std::time::Instant::now();
         let mut accumulated_time = 0.0;
+        let mut recorder: Option<Recorder> = None;
+        // A recording which failed is not restarted until the frame output asks for another recording
+        let mut failed_recording: Option<crate::Recording> = None;
         let mut events = Vec::new();
         let mut cursor_pos = None;
         let mut modifiers = Modifiers::default();
@@ -102,13 +125,21 @@ impl Window
                 *control_flow = ControlFlow::Poll;
                 match event {
                     Event::LoopDestroyed => {
+                        if let Some(recorder) = recorder.take() {
+                            if let Err(e) = recorder.finish() {
+                                error!("The recording failed: {}", e);
+                            }
+                        }
                         return;
                     }
                     Event::MainEventsCleared => {
                         let now = std::time::Instant::now();
                         let duration = now.duration_since(last_time);
                         last_time = now;
-                        let elapsed_time = duration.as_secs() as f64 * 1000.0 + duration.subsec_nanos() as f64 * 1e-6;
+                        let elapsed_time = match recorder {
+                            Some(ref recorder) => recorder.recording().time_step,
+                            None => duration.as_secs() as f64 * 1000.0 + duration.subsec_nanos() as f64 * 1e-6
+                        };
                         accumulated_time += elapsed_time;
 
                         let (physical_width, physical_height): (u32, u32) = windowed_context.window().inner_size().into();
@@ -130,12 +161,52 @@ impl Window
                         if frame_output.exit {
                             *control_flow = ControlFlow::Exit;
                         }
+                        if recorder.as_ref().map(|recorder| Some(recorder.recording()) != frame_output.recording.as_ref()).unwrap_or(false) {
+                            if let Err(e) = recorder.take().unwrap().finish() {
+                                error!("The recording failed: {}", e);
+                            }
+                        }
+                        if failed_recording.is_some() && failed_recording != frame_output.recording {
+                            failed_recording = None;
+                        }
+                        if let Some(ref recording) = frame_output.recording {
+                            if let Some(ref mut active_recorder) = recorder {
+                                if let Err(e) = active_recorder.record(&context, physical_width as usize, physical_height as usize) {
+                                    error!("The recording failed and is stopped: {}", e);
+                                    if let Err(e) = recorder.take().unwrap().finish() {
+                                        error!("The recording failed: {}", e);
+                                    }
+                                    failed_recording = Some(recording.clone());
+                                }
+                            } else if failed_recording.is_none() {
+                                // This frame was rendered with the real elapsed time, so the recording starts with the next frame
+                                recorder = Some(Recorder::new(recording.clone()));
+                            }
+                        }
                         if frame_output.swap_buffers {
                             windowed_context.swap_buffers().unwrap();
                         }
                         if let Some(ref path) = frame_output.screenshot {
-                            let pixels = crate::Screen::read_color(&context, crate::Viewport::new_at_origo(physical_width as usize, physical_height as usize)).unwrap();
-                            crate::Saver::save_pixels(path, &pixels, physical_width as usize, physical_height as usize).unwrap();
+                            let (width, height) = (physical_width as usize, physical_height as usize);
+                            let viewport = crate::Viewport::new_at_origo(width, height);
+                            match frame_output.screenshot_format {
+                                crate::ScreenshotFormat::RGB => {
+                                    let pixels = crate::Screen::read_color(&context, viewport).unwrap();
+                                    crate::Saver::save_pixels(path, &pixels, width, height).unwrap();
+                                },
+                                crate::ScreenshotFormat::RGBA => {
+                                    let pixels = crate::Screen::read_color_rgba(&context, viewport).unwrap();
+                                    crate::Saver::save_pixels_rgba(path, &pixels, width, height).unwrap();
+                                },
+                                crate::ScreenshotFormat::Depth => {
+                                    let depth = crate::Screen::read_depth(&context, viewport).unwrap();
+                                    crate::Saver::save_depth(path, &depth, width, height).unwrap();
+                                },
+                                crate::ScreenshotFormat::DepthF32 => {
+                                    let depth = crate::Screen::read_depth(&context, viewport).unwrap();
+                                    crate::Saver::save_pixels_f32(path, &depth, width, height, crate::Format::R32F).unwrap();
+                                }
+                            }
                         }
                     }
                     Event::WindowEvent { ref event, .. } => match event {
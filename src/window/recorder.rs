@@ -0,0 +1,121 @@
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use crate::frame::*;
+use crate::io::*;
+
+// Records the frames of the render loop as specified by the recording in the frame output
+pub(crate) struct Recorder {
+    recording: Recording,
+    frame_index: usize,
+    recorded_count: usize,
+    encoder: Option<Child>
+}
+
+impl Recorder {
+    pub fn new(recording: Recording) -> Self {
+        Self { recording, frame_index: 0, recorded_count: 0, encoder: None }
+    }
+
+    pub fn recording(&self) -> &Recording {
+        &self.recording
+    }
+
+    pub fn record(&mut self, context: &crate::Context, width: usize, height: usize) -> Result<(), IOError> {
+        if !self.next_frame() {
+            return Ok(());
+        }
+        let pixels = crate::Screen::read_color(context, crate::Viewport::new_at_origo(width, height))
+            .map_err(|e| IOError::FailedToSave {message: format!("Could not read the frame: {}", e)})?;
+        if let Some(ref path) = self.recording.image_path {
+            Saver::save_pixels(numbered_path(path, self.recorded_count), &pixels, width, height)?;
+        }
+        if let Some(ref command) = self.recording.encoder_command {
+            if self.encoder.is_none() {
+                self.encoder = Some(start_encoder(command, width, height, self.recording.frames_per_second())?);
+            }
+            let stdin = self.encoder.as_mut().unwrap().stdin.as_mut().unwrap();
+            // The pixels start with the bottom row, so write the rows in reverse order
+            for row in pixels.chunks(width * 3).rev() {
+                stdin.write_all(row)?;
+            }
+        }
+        self.recorded_count += 1;
+        Ok(())
+    }
+
+    // Advances to the next frame and returns whether it should be recorded according to the frame step
+    fn next_frame(&mut self) -> bool {
+        let frame_index = self.frame_index;
+        self.frame_index += 1;
+        frame_index % self.recording.frame_step.max(1) == 0
+    }
+
+    // Closes the standard input of the encoder and waits for it to finish encoding
+    pub fn finish(mut self) -> Result<(), IOError> {
+        if let Some(mut encoder) = self.encoder.take() {
+            drop(encoder.stdin.take());
+            let status = encoder.wait()?;
+            if !status.success() {
+                Err(IOError::FailedToSave {message: format!("The encoder exited with {}.", status)})?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn start_encoder(command: &[String], width: usize, height: usize, fps: f64) -> Result<Child, IOError> {
+    let arguments: Vec<String> = command.iter().map(|argument| argument
+        .replace("{width}", &width.to_string())
+        .replace("{height}", &height.to_string())
+        .replace("{fps}", &fps.to_string())).collect();
+    let (program, arguments) = arguments.split_first()
+        .ok_or_else(|| IOError::FailedToSave {message: "The encoder command is empty.".to_string()})?;
+    Command::new(program).args(arguments).stdin(Stdio::piped()).spawn()
+        .map_err(|e| IOError::FailedToSave {message: format!("Could not start the encoder {}: {}", program, e)})
+}
+
+// Replaces the sequence of '#' in the file name with the zero padded number or appends the number to the file name
+fn numbered_path(path: &std::path::Path, number: usize) -> PathBuf {
+    let file_name = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+    let file_name = if let Some(start) = file_name.find('#') {
+        let width = file_name[start..].chars().take_while(|c| *c == '#').count();
+        format!("{}{:0width$}{}", &file_name[..start], number, &file_name[start + width..], width = width)
+    } else {
+        let stem = path.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+        match path.extension() {
+            Some(extension) => format!("{}{:05}.{}", stem, number, extension.to_string_lossy()),
+            None => format!("{}{:05}", stem, number)
+        }
+    };
+    path.with_file_name(file_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn numbered_path_replaces_hashes() {
+        assert_eq!(numbered_path(Path::new("frames/turntable_####.png"), 7), Path::new("frames/turntable_0007.png"));
+        assert_eq!(numbered_path(Path::new("frame#.png"), 12), Path::new("frame12.png"));
+        assert_eq!(numbered_path(Path::new("frames/##_of_sequence.png"), 3), Path::new("frames/03_of_sequence.png"));
+    }
+
+    #[test]
+    fn numbered_path_appends_number() {
+        assert_eq!(numbered_path(Path::new("frames/turntable.png"), 7), Path::new("frames/turntable00007.png"));
+        assert_eq!(numbered_path(Path::new("frames/turntable"), 123456), Path::new("frames/turntable123456"));
+    }
+
+    #[test]
+    fn frame_step() {
+        let mut recorder = Recorder::new(Recording {frame_step: 3, ..Default::default()});
+        assert_eq!((0..7).map(|_| recorder.next_frame()).collect::<Vec<_>>(), vec![true, false, false, true, false, false, true]);
+
+        // A frame step of zero records every frame
+        let mut recorder = Recorder::new(Recording {frame_step: 0, ..Default::default()});
+        assert!((0..3).all(|_| recorder.next_frame()));
+    }
+}